@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+/// The domain of public values committed by a contract call program.
+///
+/// Must match `PUBLIC_VALUES_DOMAIN` in `sp1-cc-client-executor`.
+bytes32 constant PUBLIC_VALUES_DOMAIN = bytes32("sp1-contract-call/v1");
+
+/// The public values committed by a contract call program.
+///
+/// Must match `ContractPublicValues` in `sp1-cc-client-executor`.
+struct ContractPublicValues {
+    bytes32 domain;
+    bytes32 blockHash;
+    address callerAddress;
+    address contractAddress;
+    bytes contractCalldata;
+    bytes contractOutput;
+    bytes32 overridesDigest;
+    uint64 chainId;
+    bytes32 chainConfigHash;
+    bytes chainConfig;
+}
+
+/// How a fork activates.
+///
+/// Must match `ForkActivation` in `sp1-cc-client-executor`.
+enum ForkActivation {
+    Block,
+    TotalDifficulty,
+    Timestamp
+}
+
+/// A fork, and the block number, total difficulty or timestamp it activates at.
+///
+/// Must match `ChainFork` in `sp1-cc-client-executor`.
+struct ChainFork {
+    string name;
+    ForkActivation activation;
+    uint256 activatesAt;
+}
+
+/// The chain config a call was executed with, committed in `chainConfig` when the client opts in.
+///
+/// Must match `ChainConfigPreimage` in `sp1-cc-client-executor`.
+struct ChainConfigPreimage {
+    uint64 chainId;
+    ChainFork[] forks;
+}
+
+/// @title ContractCall
+/// @notice Helpers for verifier contracts consuming the public values of a contract call proof.
+library ContractCall {
+    /// @notice Thrown when the public values weren't committed by a contract call program.
+    error InvalidDomain();
+
+    /// @notice Thrown when the public values are for a different call than expected.
+    error UnexpectedCall();
+
+    /// @notice Thrown when the call ran against overridden state.
+    error UnexpectedOverrides();
+
+    /// @notice Thrown when the call was executed on a different chain than expected.
+    error UnexpectedChain();
+
+    /// @notice Thrown when the call was executed with a different chain config than expected.
+    error UnexpectedChainConfig();
+
+    /// @notice Thrown when the public values don't include the chain config.
+    error MissingChainConfig();
+
+    /// @notice Decodes the ABI-encoded public values of a contract call proof, checking their
+    ///         domain.
+    /// @param _publicValues The encoded public values.
+    function decode(bytes calldata _publicValues)
+        internal
+        pure
+        returns (ContractPublicValues memory publicValues)
+    {
+        publicValues = abi.decode(_publicValues, (ContractPublicValues));
+        if (publicValues.domain != PUBLIC_VALUES_DOMAIN) {
+            revert InvalidDomain();
+        }
+    }
+
+    /// @notice Checks that the public values are for a call to `_contractAddress` with
+    ///         `_contractCalldata`.
+    /// @param _publicValues The decoded public values.
+    /// @param _contractAddress The expected called contract.
+    /// @param _contractCalldata The expected calldata.
+    function verifyCall(
+        ContractPublicValues memory _publicValues,
+        address _contractAddress,
+        bytes memory _contractCalldata
+    ) internal pure {
+        if (
+            _publicValues.contractAddress != _contractAddress
+                || keccak256(_publicValues.contractCalldata) != keccak256(_contractCalldata)
+        ) {
+            revert UnexpectedCall();
+        }
+    }
+
+    /// @notice Checks that the call ran against the block's actual state, rather than a
+    ///         simulation with state overrides.
+    /// @param _publicValues The decoded public values.
+    function verifyNoOverrides(ContractPublicValues memory _publicValues) internal pure {
+        if (_publicValues.overridesDigest != bytes32(0)) {
+            revert UnexpectedOverrides();
+        }
+    }
+
+    /// @notice Checks that the call was executed on the chain with id `_chainId`.
+    /// @param _publicValues The decoded public values.
+    /// @param _chainId The expected chain id.
+    function verifyChainId(ContractPublicValues memory _publicValues, uint64 _chainId)
+        internal
+        pure
+    {
+        if (_publicValues.chainId != _chainId) {
+            revert UnexpectedChain();
+        }
+    }
+
+    /// @notice Checks that the call was executed with the chain config hashing to
+    ///         `_chainConfigHash`, as returned by `Genesis::config_hash`.
+    /// @param _publicValues The decoded public values.
+    /// @param _chainConfigHash The expected chain config hash.
+    function verifyChainConfig(ContractPublicValues memory _publicValues, bytes32 _chainConfigHash)
+        internal
+        pure
+    {
+        if (_publicValues.chainConfigHash != _chainConfigHash) {
+            revert UnexpectedChainConfig();
+        }
+    }
+
+    /// @notice Decodes the chain config committed in the public values. Only available if the
+    ///         client program committed it with `ClientExecutor::with_chain_config`.
+    /// @param _publicValues The decoded public values.
+    function decodeChainConfig(ContractPublicValues memory _publicValues)
+        internal
+        pure
+        returns (ChainConfigPreimage memory)
+    {
+        if (_publicValues.chainConfig.length == 0) {
+            revert MissingChainConfig();
+        }
+        return abi.decode(_publicValues.chainConfig, (ChainConfigPreimage));
+    }
+}
@@ -0,0 +1,73 @@
+use alloy_rlp::Encodable;
+use alloy_sol_types::SolValue;
+
+use crate::ContractPublicValues;
+
+/// An encoding for committing [`ContractPublicValues`], for verifiers that can't decode the
+/// default ABI encoding cheaply.
+pub trait PublicValuesEncoding {
+    /// Encodes `public_values` into the bytes to commit.
+    fn encode(public_values: &ContractPublicValues) -> Vec<u8>;
+}
+
+/// The Solidity ABI encoding, decodable with `abi.decode` in verifier contracts. This is the
+/// default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AbiEncoding;
+
+impl PublicValuesEncoding for AbiEncoding {
+    fn encode(public_values: &ContractPublicValues) -> Vec<u8> {
+        public_values.abi_encode()
+    }
+}
+
+/// The RLP encoding of the fields as a list, in declaration order.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RlpEncoding;
+
+impl PublicValuesEncoding for RlpEncoding {
+    fn encode(public_values: &ContractPublicValues) -> Vec<u8> {
+        let fields: [&dyn Encodable; 10] = [
+            &public_values.domain,
+            &public_values.blockHash,
+            &public_values.callerAddress,
+            &public_values.contractAddress,
+            &public_values.contractCalldata,
+            &public_values.contractOutput,
+            &public_values.overridesDigest,
+            &public_values.chainId,
+            &public_values.chainConfigHash,
+            &public_values.chainConfig,
+        ];
+
+        let mut out = Vec::new();
+        alloy_rlp::encode_list::<dyn Encodable, _>(&fields, &mut out);
+        out
+    }
+}
+
+/// The Borsh encoding of the fields as a tuple, in declaration order, for verifiers on Solana or
+/// NEAR. Addresses are 20-byte arrays and hashes 32-byte arrays.
+#[cfg(feature = "borsh")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BorshEncoding;
+
+#[cfg(feature = "borsh")]
+impl PublicValuesEncoding for BorshEncoding {
+    fn encode(public_values: &ContractPublicValues) -> Vec<u8> {
+        let fields = (
+            public_values.domain.0,
+            public_values.blockHash.0,
+            public_values.callerAddress.0 .0,
+            public_values.contractAddress.0 .0,
+            public_values.contractCalldata.to_vec(),
+            public_values.contractOutput.to_vec(),
+            public_values.overridesDigest.0,
+            public_values.chainId,
+            public_values.chainConfigHash.0,
+            public_values.chainConfig.to_vec(),
+        );
+
+        borsh::to_vec(&fields).expect("writing to a vec can't fail")
+    }
+}
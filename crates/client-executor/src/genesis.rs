@@ -0,0 +1,276 @@
+use std::path::Path;
+
+use alloy_genesis::ChainConfig;
+use alloy_sol_types::{sol, SolValue};
+use reth_chainspec::{ChainSpec, EthereumHardfork, ForkCondition};
+use revm_primitives::{keccak256, B256, U256};
+use serde::{Deserialize, Serialize};
+
+sol! {
+    /// How a [`ChainFork`] activates.
+    enum ForkActivation {
+        Block,
+        TotalDifficulty,
+        Timestamp,
+    }
+
+    /// A fork, and the block number, total difficulty or timestamp it activates at.
+    struct ChainFork {
+        string name;
+        ForkActivation activation;
+        uint256 activatesAt;
+    }
+
+    /// The chain config calls are executed with: the chain id, and the forks scheduled on the
+    /// chain in activation order.
+    ///
+    /// Its ABI encoding is [`Genesis::config_preimage`], which can be committed in the public
+    /// values for verifiers that want to read the chain config rather than compare its hash.
+    struct ChainConfigPreimage {
+        uint64 chainId;
+        ChainFork[] forks;
+    }
+}
+
+/// The forks included in a [`ChainConfigPreimage`], in activation order.
+const FORKS: [(&str, EthereumHardfork); 18] = [
+    ("Frontier", EthereumHardfork::Frontier),
+    ("Homestead", EthereumHardfork::Homestead),
+    ("Dao", EthereumHardfork::Dao),
+    ("Tangerine", EthereumHardfork::Tangerine),
+    ("SpuriousDragon", EthereumHardfork::SpuriousDragon),
+    ("Byzantium", EthereumHardfork::Byzantium),
+    ("Constantinople", EthereumHardfork::Constantinople),
+    ("Petersburg", EthereumHardfork::Petersburg),
+    ("Istanbul", EthereumHardfork::Istanbul),
+    ("MuirGlacier", EthereumHardfork::MuirGlacier),
+    ("Berlin", EthereumHardfork::Berlin),
+    ("London", EthereumHardfork::London),
+    ("ArrowGlacier", EthereumHardfork::ArrowGlacier),
+    ("GrayGlacier", EthereumHardfork::GrayGlacier),
+    ("Paris", EthereumHardfork::Paris),
+    ("Shanghai", EthereumHardfork::Shanghai),
+    ("Cancun", EthereumHardfork::Cancun),
+    ("Prague", EthereumHardfork::Prague),
+];
+
+/// The chain a block belongs to, which determines the [`ChainSpec`] calls are executed with.
+///
+/// The built-in chains know when Prague activated, but blocks from then on are rejected, since
+/// revm 14 only implements a pre-final draft of it. See [`crate::check_fork_supported`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Genesis {
+    /// Ethereum mainnet.
+    #[default]
+    Mainnet,
+    /// The Sepolia testnet.
+    Sepolia,
+    /// The Holesky testnet.
+    Holesky,
+    /// The Hoodi testnet.
+    Hoodi,
+    /// Any other chain, described by its geth-style chain config.
+    Custom(#[serde(with = "chain_config_json")] Box<ChainConfig>),
+}
+
+impl Genesis {
+    /// Parses a geth-style genesis file, or just its `config` object, into a [`Genesis::Custom`].
+    pub fn from_json(json: &str) -> eyre::Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        let config: ChainConfig = match value.get("config") {
+            Some(config) => serde_json::from_value(config.clone())?,
+            None => serde_json::from_value(value)?,
+        };
+        Ok(Self::Custom(Box::new(config)))
+    }
+
+    /// Reads a geth-style genesis file from `path`. See [`Genesis::from_json`].
+    pub fn from_file(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// The chains with a built-in [`Genesis`].
+    pub const KNOWN: [Genesis; 4] = [Self::Mainnet, Self::Sepolia, Self::Holesky, Self::Hoodi];
+
+    /// Returns the built-in [`Genesis`] with the given chain id, if any.
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        Self::KNOWN.into_iter().find(|genesis| genesis.chain_id() == chain_id)
+    }
+
+    /// Returns the chain config of the chain, as derived from its [`ChainSpec`].
+    ///
+    /// Built-in and custom chains are described the same way, so a built-in chain and a custom
+    /// chain with the same fork schedule have the same config, and changing the fork schedule of a
+    /// built-in chain changes it.
+    pub fn chain_config(&self) -> ChainConfigPreimage {
+        let chain_spec = self.chain_spec();
+        let forks = FORKS
+            .into_iter()
+            .filter_map(|(name, fork)| {
+                let (activation, activates_at) = match chain_spec.fork(fork) {
+                    ForkCondition::Block(block) => (ForkActivation::Block, U256::from(block)),
+                    ForkCondition::TTD { total_difficulty, .. } => {
+                        (ForkActivation::TotalDifficulty, total_difficulty)
+                    }
+                    ForkCondition::Timestamp(timestamp) => {
+                        (ForkActivation::Timestamp, U256::from(timestamp))
+                    }
+                    ForkCondition::Never => return None,
+                };
+                Some(ChainFork { name: name.to_string(), activation, activatesAt: activates_at })
+            })
+            .collect();
+
+        ChainConfigPreimage { chainId: self.chain_id(), forks }
+    }
+
+    /// Returns the canonical encoding of the chain config, whose hash is committed in the public
+    /// values. It is the ABI encoding of [`Genesis::chain_config`].
+    pub fn config_preimage(&self) -> Vec<u8> {
+        self.chain_config().abi_encode()
+    }
+
+    /// Returns the keccak hash of [`Genesis::config_preimage`].
+    pub fn config_hash(&self) -> B256 {
+        keccak256(self.config_preimage())
+    }
+
+    /// Returns the [`ChainSpec`] calls on the chain are executed with.
+    pub fn chain_spec(&self) -> ChainSpec {
+        match self {
+            Self::Mainnet => {
+                let mut spec = rsp_primitives::chain_spec::mainnet();
+                spec.hardforks.insert(
+                    EthereumHardfork::Prague,
+                    ForkCondition::Timestamp(MAINNET_PRAGUE_TIME),
+                );
+                spec
+            }
+            Self::Sepolia => chain_spec_from_config(sepolia_config()),
+            Self::Holesky => chain_spec_from_config(holesky_config()),
+            Self::Hoodi => chain_spec_from_config(hoodi_config()),
+            Self::Custom(config) => chain_spec_from_config((**config).clone()),
+        }
+    }
+
+    /// Returns the chain id of the chain.
+    pub fn chain_id(&self) -> u64 {
+        match self {
+            Self::Mainnet => 1,
+            Self::Sepolia => 11155111,
+            Self::Holesky => 17000,
+            Self::Hoodi => 560048,
+            Self::Custom(config) => config.chain_id,
+        }
+    }
+}
+
+impl TryFrom<&Genesis> for ChainSpec {
+    type Error = eyre::Error;
+
+    fn try_from(genesis: &Genesis) -> eyre::Result<Self> {
+        Ok(genesis.chain_spec())
+    }
+}
+
+/// The timestamp Prague activated at on mainnet.
+const MAINNET_PRAGUE_TIME: u64 = 1746612311;
+
+/// Builds a [`ChainSpec`] from a chain config alone.
+///
+/// The genesis allocations aren't needed to execute calls, so they are left empty. This avoids
+/// parsing large genesis files in the zkVM.
+fn chain_spec_from_config(config: ChainConfig) -> ChainSpec {
+    alloy_genesis::Genesis { config, ..Default::default() }.into()
+}
+
+/// The chain config of the Sepolia testnet.
+fn sepolia_config() -> ChainConfig {
+    ChainConfig {
+        chain_id: 11155111,
+        homestead_block: Some(0),
+        eip150_block: Some(0),
+        eip155_block: Some(0),
+        eip158_block: Some(0),
+        byzantium_block: Some(0),
+        constantinople_block: Some(0),
+        petersburg_block: Some(0),
+        istanbul_block: Some(0),
+        muir_glacier_block: Some(0),
+        berlin_block: Some(0),
+        london_block: Some(0),
+        merge_netsplit_block: Some(1735371),
+        terminal_total_difficulty: Some(U256::from(17_000_000_000_000_000u64)),
+        terminal_total_difficulty_passed: true,
+        shanghai_time: Some(1677557088),
+        cancun_time: Some(1706655072),
+        prague_time: Some(1741159776),
+        ..Default::default()
+    }
+}
+
+/// The chain config of the Holesky testnet.
+fn holesky_config() -> ChainConfig {
+    ChainConfig {
+        chain_id: 17000,
+        shanghai_time: Some(1696000704),
+        cancun_time: Some(1707305664),
+        prague_time: Some(1740434112),
+        ..post_merge_genesis_config()
+    }
+}
+
+/// The chain config of the Hoodi testnet.
+fn hoodi_config() -> ChainConfig {
+    ChainConfig {
+        chain_id: 560048,
+        shanghai_time: Some(0),
+        cancun_time: Some(0),
+        prague_time: Some(1742999832),
+        ..post_merge_genesis_config()
+    }
+}
+
+/// A chain config with every fork up to the merge active at genesis.
+fn post_merge_genesis_config() -> ChainConfig {
+    ChainConfig {
+        homestead_block: Some(0),
+        eip150_block: Some(0),
+        eip155_block: Some(0),
+        eip158_block: Some(0),
+        byzantium_block: Some(0),
+        constantinople_block: Some(0),
+        petersburg_block: Some(0),
+        istanbul_block: Some(0),
+        berlin_block: Some(0),
+        london_block: Some(0),
+        merge_netsplit_block: Some(0),
+        terminal_total_difficulty: Some(U256::ZERO),
+        terminal_total_difficulty_passed: true,
+        ..Default::default()
+    }
+}
+
+/// Serializes a [`ChainConfig`] as a JSON string.
+///
+/// The config skips empty fields when serialized, which non self-describing formats like bincode
+/// can't deserialize.
+mod chain_config_json {
+    use alloy_genesis::ChainConfig;
+    use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        config: &ChainConfig,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let json = serde_json::to_string(config).map_err(S::Error::custom)?;
+        serializer.serialize_str(&json)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Box<ChainConfig>, D::Error> {
+        let json = String::deserialize(deserializer)?;
+        serde_json::from_str(&json).map_err(D::Error::custom)
+    }
+}
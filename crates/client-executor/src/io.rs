@@ -6,6 +6,8 @@ use rsp_client_executor::io::WitnessInput;
 use rsp_mpt::EthereumState;
 use serde::{Deserialize, Serialize};
 
+use crate::genesis::Genesis;
+
 /// Information about how the contract executions accessed state, which is needed to execute the
 /// contract in SP1.
 ///
@@ -13,6 +15,8 @@ use serde::{Deserialize, Serialize};
 /// for the storage slots that were modified and accessed are passed in.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct EVMStateSketch {
+    /// The chain the block belongs to.
+    pub genesis: Genesis,
     /// The current block header.
     pub header: Header,
     /// The previous block headers starting from the most recent. These are used for calls to the
@@ -52,3 +56,50 @@ impl WitnessInput for EVMStateSketch {
         once(&self.header).chain(self.ancestor_headers.iter())
     }
 }
+
+impl EVMStateSketch {
+    /// Serializes the sketch with the encoding expected by [`EVMStateSketch::from_bytes`].
+    ///
+    /// Write these bytes to the SP1 stdin as a `Vec<u8>`, and decode them in the client with
+    /// [`EVMStateSketch::from_bytes`].
+    pub fn to_bytes(&self) -> eyre::Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Deserializes a sketch produced by [`EVMStateSketch::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> eyre::Result<Self> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+
+    /// Returns statistics about the size and composition of the sketch.
+    pub fn stats(&self) -> eyre::Result<SketchStats> {
+        Ok(SketchStats {
+            total_bytes: bincode::serialized_size(self)?,
+            state_bytes: bincode::serialized_size(&self.state)?,
+            accounts: self.state_requests.len(),
+            storage_slots: self.state_requests.values().map(Vec::len).sum(),
+            bytecodes: self.bytecodes.len(),
+            bytecode_bytes: self.bytecodes.iter().map(Bytecode::len).sum(),
+            headers: 1 + self.ancestor_headers.len(),
+        })
+    }
+}
+
+/// Statistics about an [`EVMStateSketch`], to help understand what makes up a witness.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SketchStats {
+    /// The size of the serialized sketch, in bytes.
+    pub total_bytes: u64,
+    /// The size of the serialized state tries, in bytes.
+    pub state_bytes: u64,
+    /// The number of accounts accessed.
+    pub accounts: usize,
+    /// The number of storage slots accessed, across all accounts.
+    pub storage_slots: usize,
+    /// The number of bytecodes.
+    pub bytecodes: usize,
+    /// The total size of the bytecodes, in bytes.
+    pub bytecode_bytes: usize,
+    /// The number of headers, including the current one.
+    pub headers: usize,
+}
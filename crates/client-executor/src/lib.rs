@@ -1,14 +1,27 @@
+#[cfg(test)]
+mod test;
+
+pub mod encoding;
+pub mod genesis;
 pub mod io;
-use alloy_sol_types::{sol, SolCall};
+use std::{collections::BTreeMap, fmt};
+
+use alloy_sol_types::{sol, SolCall, SolValue};
+use encoding::PublicValuesEncoding;
 use eyre::OptionExt;
+use genesis::Genesis;
 use io::EVMStateSketch;
+use reth_chainspec::{ChainSpec, EthereumHardfork};
 use reth_evm::ConfigureEvmEnv;
 use reth_evm_ethereum::EthEvmConfig;
 use reth_primitives::Header;
-use revm::{db::CacheDB, Database, Evm, EvmBuilder, State};
-use revm_primitives::{Address, BlockEnv, Bytes, CfgEnvWithHandlerCfg, SpecId, TxKind, B256, U256};
+use revm::{db::CacheDB, Database, DatabaseRef, Evm, EvmBuilder, State};
+use revm_primitives::{
+    keccak256, Address, BlockEnv, Bytecode, Bytes, CfgEnvWithHandlerCfg, SpecId, TxKind, B256, U256,
+};
 use rsp_client_executor::io::WitnessInput;
 use rsp_witness_db::WitnessDb;
+use serde::{Deserialize, Serialize};
 
 /// Input to a contract call.
 ///
@@ -21,6 +34,53 @@ pub struct ContractInput {
     pub caller_address: Address,
     /// The calldata to pass to the contract.
     pub calldata: ContractCalldata,
+    /// Overrides applied to the state of some accounts before the call is executed. The host and
+    /// the client must execute the call with the same overrides.
+    pub overrides: BTreeMap<Address, AccountOverride>,
+}
+
+/// An override of an account's state, applied before executing a [`ContractInput`].
+///
+/// Fields that are `None` keep their value from the block's state. Storage slots that aren't
+/// overridden are left untouched.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountOverride {
+    /// The balance to set.
+    pub balance: Option<U256>,
+    /// The nonce to set.
+    pub nonce: Option<u64>,
+    /// The bytecode to set.
+    pub code: Option<Bytes>,
+    /// The storage slots to set.
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl AccountOverride {
+    /// Applies the overrides to the account at `address` in `db`.
+    pub fn apply<DB: DatabaseRef>(
+        &self,
+        db: &mut CacheDB<DB>,
+        address: Address,
+    ) -> Result<(), DB::Error> {
+        let mut info = db.basic(address)?.unwrap_or_default();
+        if let Some(balance) = self.balance {
+            info.balance = balance;
+        }
+        if let Some(nonce) = self.nonce {
+            info.nonce = nonce;
+        }
+        if let Some(code) = &self.code {
+            let bytecode = Bytecode::new_raw(code.clone());
+            info.code_hash = bytecode.hash_slow();
+            info.code = Some(bytecode);
+        }
+        db.insert_account_info(address, info);
+
+        for (slot, value) in self.storage.iter() {
+            db.insert_account_storage(address, *slot, *value)?;
+        }
+        Ok(())
+    }
 }
 
 /// The type of calldata to pass to a contract.
@@ -53,6 +113,21 @@ impl ContractInput {
             contract_address,
             caller_address,
             calldata: ContractCalldata::Call(calldata.abi_encode().into()),
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    /// Create a new contract call input from already encoded calldata.
+    pub fn new_raw_call(
+        contract_address: Address,
+        caller_address: Address,
+        calldata: Bytes,
+    ) -> Self {
+        Self {
+            contract_address,
+            caller_address,
+            calldata: ContractCalldata::Call(calldata),
+            overrides: BTreeMap::new(),
         }
     }
 
@@ -65,30 +140,97 @@ impl ContractInput {
             contract_address: Address::ZERO,
             caller_address,
             calldata: ContractCalldata::Create(calldata),
+            overrides: BTreeMap::new(),
+        }
+    }
+
+    /// Overrides the state of the account at `address` for this call.
+    pub fn with_override(mut self, address: Address, account_override: AccountOverride) -> Self {
+        self.overrides.insert(address, account_override);
+        self
+    }
+
+    /// Applies the state overrides of this call to `db`.
+    pub fn apply_overrides<DB: DatabaseRef>(&self, db: &mut CacheDB<DB>) -> Result<(), DB::Error> {
+        for (address, account_override) in self.overrides.iter() {
+            account_override.apply(db, *address)?;
         }
+        Ok(())
+    }
+
+    /// Returns a digest of the state overrides of this call, or zero if there are none.
+    ///
+    /// It is the keccak hash of the ABI encoding of a `(address, bool, uint256, bool, uint64, bool,
+    /// bytes, (uint256, uint256)[])[]` array, with one entry per overridden account in address
+    /// order. The booleans tell whether the balance, nonce and code are overridden.
+    pub fn overrides_digest(&self) -> B256 {
+        if self.overrides.is_empty() {
+            return B256::ZERO;
+        }
+
+        let overrides = self
+            .overrides
+            .iter()
+            .map(|(address, account_override)| {
+                (
+                    *address,
+                    account_override.balance.is_some(),
+                    account_override.balance.unwrap_or_default(),
+                    account_override.nonce.is_some(),
+                    account_override.nonce.unwrap_or_default(),
+                    account_override.code.is_some(),
+                    account_override.code.clone().unwrap_or_default(),
+                    account_override.storage.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+                )
+            })
+            .collect::<Vec<_>>();
+        keccak256(overrides.abi_encode())
     }
 }
 
+/// Identifies public values committed by this crate, so that verifiers can't mistake the output
+/// of another program or encoding version for a contract call. It is the ASCII string
+/// `sp1-contract-call/v1`, right-padded with zeros like Solidity's `bytes32("...")`.
+pub const PUBLIC_VALUES_DOMAIN: B256 = B256::new(*b"sp1-contract-call/v1\0\0\0\0\0\0\0\0\0\0\0\0");
+
 sol! {
     /// Public values of a contract call.
     ///
-    /// These outputs can easily be abi-encoded, for use on-chain.
+    /// These outputs can easily be abi-encoded, for use on-chain. `domain` is always
+    /// [`PUBLIC_VALUES_DOMAIN`]. `overridesDigest` is [`ContractInput::overrides_digest`], which
+    /// is zero unless the call ran against overridden state. `chainId` is the chain the call was
+    /// executed on, and `chainConfigHash` is the [`Genesis::config_hash`] of its chain config.
+    /// `chainConfig` is the [`Genesis::config_preimage`] itself if the client opted into committing
+    /// it with [`ClientExecutor::with_chain_config`], and empty otherwise.
+    ///
+    /// The JSON representation keeps the Solidity field names, for off-chain consumers.
+    #[derive(Serialize, Deserialize)]
     struct ContractPublicValues {
+        bytes32 domain;
         bytes32 blockHash;
         address callerAddress;
         address contractAddress;
         bytes contractCalldata;
         bytes contractOutput;
+        bytes32 overridesDigest;
+        uint64 chainId;
+        bytes32 chainConfigHash;
+        bytes chainConfig;
     }
 }
 
 impl ContractPublicValues {
     /// Construct a new [`ContractPublicValues`]
     ///
-    /// By default, commit the contract input, the output, and the block hash to public values of
-    /// the proof. More can be committed if necessary.
-    pub fn new(call: ContractInput, output: Bytes, block_hash: B256) -> Self {
+    /// By default, commit the contract input, the output, the block hash and the chain to public
+    /// values of the proof. More can be committed if necessary.
+    pub fn new(call: ContractInput, output: Bytes, block_hash: B256, genesis: &Genesis) -> Self {
         Self {
+            chainId: genesis.chain_id(),
+            chainConfigHash: genesis.config_hash(),
+            chainConfig: Bytes::new(),
+            overridesDigest: call.overrides_digest(),
+            domain: PUBLIC_VALUES_DOMAIN,
             contractAddress: call.contract_address,
             callerAddress: call.caller_address,
             contractCalldata: call.calldata.to_bytes(),
@@ -96,6 +238,28 @@ impl ContractPublicValues {
             blockHash: block_hash,
         }
     }
+
+    /// Encodes the public values with `E`, e.g. [`RlpEncoding`](encoding::RlpEncoding) for
+    /// verifiers that don't decode ABI. Use `abi_encode` for the default encoding.
+    pub fn encode<E: PublicValuesEncoding>(&self) -> Vec<u8> {
+        E::encode(self)
+    }
+}
+
+impl fmt::Display for ContractPublicValues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let domain = String::from_utf8_lossy(self.domain.as_slice());
+        writeln!(f, "domain:     {}", domain.trim_end_matches('\0'))?;
+        writeln!(f, "block hash: {}", self.blockHash)?;
+        writeln!(f, "caller:     {}", self.callerAddress)?;
+        writeln!(f, "contract:   {}", self.contractAddress)?;
+        writeln!(f, "calldata:   {}", self.contractCalldata)?;
+        writeln!(f, "output:     {}", self.contractOutput)?;
+        writeln!(f, "overrides:  {}", self.overridesDigest)?;
+        writeln!(f, "chain id:   {}", self.chainId)?;
+        writeln!(f, "chain hash: {}", self.chainConfigHash)?;
+        write!(f, "config:     {}", self.chainConfig)
+    }
 }
 
 /// An executor that executes smart contract calls inside a zkVM.
@@ -105,33 +269,73 @@ pub struct ClientExecutor {
     pub witness_db: WitnessDb,
     /// The block header.
     pub header: Header,
+    /// The chain the block belongs to.
+    pub genesis: Genesis,
+    /// The chain spec calls are executed with.
+    pub chain_spec: ChainSpec,
+    /// Whether the full chain config is committed in the public values, besides its hash.
+    pub commit_chain_config: bool,
 }
 
 impl ClientExecutor {
-    /// Instantiates a new [`ClientExecutor`]
-    pub fn new(state_sketch: EVMStateSketch) -> eyre::Result<Self> {
-        // let header = state_sketch.header.clone();
-        Ok(Self { witness_db: state_sketch.witness_db().unwrap(), header: state_sketch.header })
+    /// Instantiates a new [`ClientExecutor`] for a block of the chain described by `genesis`.
+    ///
+    /// The chain config decides which opcodes and gas rules apply, and isn't covered by the block
+    /// hash, so the client program fixes it rather than trusting the sketch. Returns an error if
+    /// the sketch was built for another chain.
+    pub fn new(state_sketch: EVMStateSketch, genesis: &Genesis) -> eyre::Result<Self> {
+        if state_sketch.genesis != *genesis {
+            eyre::bail!(
+                "sketch was built for chain {}, expected chain {}",
+                state_sketch.genesis.chain_id(),
+                genesis.chain_id()
+            );
+        }
+        let chain_spec = ChainSpec::try_from(genesis)?;
+        check_fork_supported(&state_sketch.header, &chain_spec)?;
+        Ok(Self {
+            witness_db: state_sketch.witness_db().unwrap(),
+            header: state_sketch.header,
+            genesis: genesis.clone(),
+            chain_spec,
+            commit_chain_config: false,
+        })
+    }
+
+    /// Commits the full chain config in the `chainConfig` field of the public values, so that
+    /// verifiers can read it rather than only compare its hash.
+    pub fn with_chain_config(mut self) -> Self {
+        self.commit_chain_config = true;
+        self
     }
 
     /// Executes the smart contract call with the given [`ContractInput`] in SP1.
     ///
     /// Storage accesses are already validated against the `witness_db`'s state root.
     pub fn execute(&self, call: ContractInput) -> eyre::Result<ContractPublicValues> {
-        let cache_db = CacheDB::new(&self.witness_db);
-        let mut evm = new_evm(cache_db, &self.header, U256::ZERO, &call);
+        let mut cache_db = CacheDB::new(&self.witness_db);
+        call.apply_overrides(&mut cache_db)?;
+        let mut evm = new_evm(cache_db, &self.header, &self.chain_spec, &call);
         let tx_output = evm.transact()?;
         let tx_output_bytes = tx_output.result.output().ok_or_eyre("Error decoding result")?;
-        Ok(ContractPublicValues::new(call, tx_output_bytes.clone(), self.header.hash_slow()))
+        let mut public_values = ContractPublicValues::new(
+            call,
+            tx_output_bytes.clone(),
+            self.header.hash_slow(),
+            &self.genesis,
+        );
+        if self.commit_chain_config {
+            public_values.chainConfig = self.genesis.config_preimage().into();
+        }
+        Ok(public_values)
     }
 }
 
-/// TODO Add support for other chains besides Ethereum Mainnet.
 /// Instantiates a new EVM, which is ready to run `call`.
 pub fn new_evm<'a, D>(
     db: D,
     header: &Header,
-    total_difficulty: U256,
+    chain_spec: &ChainSpec,
     call: &ContractInput,
 ) -> Evm<'a, (), State<D>>
 where
@@ -143,9 +347,9 @@ where
     EthEvmConfig::default().fill_cfg_and_block_env(
         &mut cfg_env,
         &mut block_env,
-        &rsp_primitives::chain_spec::mainnet(),
+        chain_spec,
         header,
-        total_difficulty,
+        total_difficulty(header, chain_spec),
     );
     // Set the base fee to 0 to enable 0 gas price transactions.
     block_env.basefee = U256::from(0);
@@ -170,3 +374,31 @@ where
     };
     evm
 }
+
+/// Returns an error if `header` is from a fork revm doesn't fully implement.
+///
+/// revm 14 only implements a pre-final draft of Prague: the BLS precompiles of EIP-2537 live at
+/// other addresses and the EIP-7691 blob limits are missing. Calls on Prague blocks could succeed
+/// with results that never happened on chain, so they are rejected instead.
+pub fn check_fork_supported(header: &Header, chain_spec: &ChainSpec) -> eyre::Result<()> {
+    if chain_spec.fork(EthereumHardfork::Prague).active_at_timestamp(header.timestamp) {
+        eyre::bail!("block {} is after the Prague fork, which isn't supported yet", header.number);
+    }
+    Ok(())
+}
+
+/// Returns the total difficulty used to select the spec of `header`.
+///
+/// The total difficulty isn't part of the header, so it is derived from it instead of being
+/// trusted from the witness: post-merge blocks have a zero difficulty and get the chain's final
+/// Paris total difficulty, which activates Paris, and earlier blocks get zero.
+pub fn total_difficulty(header: &Header, chain_spec: &ChainSpec) -> U256 {
+    if !header.difficulty.is_zero() {
+        return U256::ZERO;
+    }
+
+    chain_spec
+        .get_final_paris_total_difficulty()
+        .or_else(|| chain_spec.fork(EthereumHardfork::Paris).ttd())
+        .unwrap_or_default()
+}
@@ -0,0 +1,143 @@
+use alloy_sol_types::SolValue;
+use revm_primitives::{hex, keccak256, Address, Bytes, B256, U256};
+
+#[cfg(feature = "borsh")]
+use crate::encoding::BorshEncoding;
+use crate::{
+    encoding::RlpEncoding,
+    genesis::{ChainConfigPreimage, ForkActivation, Genesis},
+    AccountOverride, ContractInput, ContractPublicValues, PUBLIC_VALUES_DOMAIN,
+};
+
+/// This tests that custom chain configs survive the bincode encoding used to pass sketches to the
+/// client.
+#[test]
+fn test_custom_genesis_roundtrip() -> eyre::Result<()> {
+    let genesis = Genesis::from_json(
+        r#"{
+            "config": {
+                "chainId": 1337,
+                "homesteadBlock": 0,
+                "londonBlock": 0,
+                "terminalTotalDifficulty": 0,
+                "shanghaiTime": 0
+            },
+            "alloc": {}
+        }"#,
+    )?;
+    assert_eq!(genesis.chain_id(), 1337);
+
+    let decoded: Genesis = bincode::deserialize(&bincode::serialize(&genesis)?)?;
+    assert_eq!(decoded, genesis);
+
+    Ok(())
+}
+
+#[test]
+fn test_overrides_digest() {
+    let call = ContractInput::new_raw_call(Address::ZERO, Address::ZERO, Bytes::new());
+    assert_eq!(call.overrides_digest(), B256::ZERO);
+
+    let account_override =
+        AccountOverride { storage: [(U256::ZERO, U256::from(1))].into(), ..Default::default() };
+    let overridden = call.clone().with_override(Address::repeat_byte(1), account_override.clone());
+    let digest = overridden.overrides_digest();
+    assert_ne!(digest, B256::ZERO);
+    assert_eq!(overridden.clone().overrides_digest(), digest);
+    assert_eq!(
+        call.clone()
+            .with_override(Address::repeat_byte(1), account_override.clone())
+            .overrides_digest(),
+        digest
+    );
+
+    // Changing any part of an override changes the digest.
+    let other_address = call.clone().with_override(Address::repeat_byte(2), account_override);
+    assert_ne!(other_address.overrides_digest(), digest);
+    let other_value = call.clone().with_override(
+        Address::repeat_byte(1),
+        AccountOverride { storage: [(U256::ZERO, U256::from(2))].into(), ..Default::default() },
+    );
+    assert_ne!(other_value.overrides_digest(), digest);
+    let zero_balance = call.with_override(
+        Address::repeat_byte(1),
+        AccountOverride {
+            balance: Some(U256::ZERO),
+            storage: [(U256::ZERO, U256::from(1))].into(),
+            ..Default::default()
+        },
+    );
+    assert_ne!(zero_balance.overrides_digest(), digest);
+}
+
+/// Public values with a distinct, easily recognizable value in every field.
+fn golden_public_values() -> ContractPublicValues {
+    ContractPublicValues {
+        domain: PUBLIC_VALUES_DOMAIN,
+        blockHash: B256::repeat_byte(0x11),
+        callerAddress: Address::repeat_byte(0x22),
+        contractAddress: Address::repeat_byte(0x33),
+        contractCalldata: Bytes::from_static(&[0xde, 0xad, 0xbe, 0xef]),
+        contractOutput: Bytes::from_static(&[0x2a]),
+        overridesDigest: B256::ZERO,
+        chainId: 1,
+        chainConfigHash: B256::repeat_byte(0x44),
+        chainConfig: Bytes::from_static(&[0xc0, 0xfe]),
+    }
+}
+
+#[test]
+fn test_rlp_encoding() {
+    let expected = concat!(
+        "f8b8",
+        "a07370312d636f6e74726163742d63616c6c2f7631000000000000000000000000",
+        "a01111111111111111111111111111111111111111111111111111111111111111",
+        "942222222222222222222222222222222222222222",
+        "943333333333333333333333333333333333333333",
+        "84deadbeef",
+        "2a",
+        "a00000000000000000000000000000000000000000000000000000000000000000",
+        "01",
+        "a04444444444444444444444444444444444444444444444444444444444444444",
+        "82c0fe",
+    );
+    assert_eq!(hex::encode(golden_public_values().encode::<RlpEncoding>()), expected);
+}
+
+#[cfg(feature = "borsh")]
+#[test]
+fn test_borsh_encoding() {
+    let expected = concat!(
+        "7370312d636f6e74726163742d63616c6c2f7631000000000000000000000000",
+        "1111111111111111111111111111111111111111111111111111111111111111",
+        "2222222222222222222222222222222222222222",
+        "3333333333333333333333333333333333333333",
+        "04000000deadbeef",
+        "010000002a",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0100000000000000",
+        "4444444444444444444444444444444444444444444444444444444444444444",
+        "02000000c0fe",
+    );
+    assert_eq!(hex::encode(golden_public_values().encode::<BorshEncoding>()), expected);
+}
+
+#[test]
+fn test_chain_config_hash() -> eyre::Result<()> {
+    let config = Genesis::Mainnet.chain_config();
+    assert_eq!(config.chainId, 1);
+    let prague = config.forks.iter().find(|fork| fork.name == "Prague").expect("no Prague fork");
+    assert!(matches!(prague.activation, ForkActivation::Timestamp));
+    assert_eq!(prague.activatesAt, U256::from(1746612311));
+
+    // The committed hash is the hash of the preimage, which decodes back to the config.
+    let preimage = Genesis::Mainnet.config_preimage();
+    assert_eq!(keccak256(&preimage), Genesis::Mainnet.config_hash());
+    let decoded = ChainConfigPreimage::abi_decode(&preimage, true)?;
+    assert_eq!(decoded.chainId, config.chainId);
+    assert_eq!(decoded.forks.len(), config.forks.len());
+
+    assert_ne!(Genesis::Sepolia.config_hash(), Genesis::Mainnet.config_hash());
+
+    Ok(())
+}
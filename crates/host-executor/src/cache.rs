@@ -0,0 +1,213 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use alloy_json_rpc::{
+    Id, RequestPacket, Response, ResponsePacket, ResponsePayload, SerializedRequest,
+};
+use alloy_primitives::{keccak256, B256, U64};
+use alloy_transport::{TransportError, TransportFut};
+use serde::Deserialize;
+use serde_json::{value::RawValue, Value};
+use tower::{Layer, Service};
+
+/// Methods whose responses only depend on their parameters, as long as those pin a block.
+///
+/// `eth_getBlockByNumber` is deliberately missing: the block at a height changes on a reorg, and
+/// fetching it fresh is what lets [`crate::HostExecutor::check_reorg`] notice.
+const CACHEABLE_METHODS: [&str; 6] = [
+    "eth_getProof",
+    "eth_getCode",
+    "eth_getStorageAt",
+    "eth_getBalance",
+    "eth_getTransactionCount",
+    "eth_getBlockByHash",
+];
+
+/// A [`Layer`] that caches JSON-RPC responses on disk.
+///
+/// Only requests for state at a fixed block are cached, including batches made up entirely of
+/// such requests. Re-running the host against the same block (common during development) is then
+/// served from disk instead of the endpoint.
+///
+/// The cache is keyed by the request and the hash of the block it refers to. The hash of a block
+/// number is learned from the `eth_getBlockByNumber` responses passing through the layer, which
+/// are never cached, and requests for a block whose hash isn't known yet aren't cached either.
+/// [`crate::HostExecutor::new`] looks the block up first, so all of its state requests are
+/// cached, and state cached for a block that was reorged out is never served for its
+/// replacement.
+#[derive(Debug, Clone)]
+pub struct DiskCacheLayer {
+    dir: PathBuf,
+}
+
+impl DiskCacheLayer {
+    /// Creates a new [`DiskCacheLayer`] storing responses in `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl<S> Layer<S> for DiskCacheLayer {
+    type Service = DiskCacheService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        DiskCacheService { inner, dir: self.dir.clone(), block_hashes: Default::default() }
+    }
+}
+
+/// The service created by a [`DiskCacheLayer`]. Clones share the block hashes learned so far.
+#[derive(Debug, Clone)]
+pub struct DiskCacheService<S> {
+    inner: S,
+    dir: PathBuf,
+    block_hashes: Arc<Mutex<HashMap<u64, B256>>>,
+}
+
+impl<S> DiskCacheService<S> {
+    /// Returns the cache file for `request`, or `None` if its response can't be cached.
+    fn cache_path(&self, request: &SerializedRequest) -> Option<PathBuf> {
+        if !CACHEABLE_METHODS.contains(&request.method()) {
+            return None;
+        }
+        let params = request.params().map(RawValue::get).unwrap_or_default();
+        // `eth_getBlockByHash` already names its block by hash.
+        let block_hash = match request.method() {
+            "eth_getBlockByHash" => B256::ZERO,
+            _ => self.block_hash(params)?,
+        };
+
+        let key = keccak256(
+            [request.method().as_bytes(), params.as_bytes(), block_hash.as_slice()].concat(),
+        );
+        Some(self.dir.join(format!("{key:x}.json")))
+    }
+
+    /// Returns the hash of the block `params` refer to, if it's a fixed block whose hash is known.
+    ///
+    /// The block is the last parameter, either as a number, or as an EIP-1898 object holding its
+    /// number or hash. Tags such as `latest` are never resolved.
+    fn block_hash(&self, params: &str) -> Option<B256> {
+        let params: Vec<Value> = serde_json::from_str(params).ok()?;
+        let block = params.last()?;
+        if let Some(hash) = block.get("blockHash") {
+            return serde_json::from_value(hash.clone()).ok();
+        }
+
+        let number = block.get("blockNumber").unwrap_or(block).as_str()?;
+        let number = u64::from_str_radix(number.strip_prefix("0x")?, 16).ok()?;
+        self.block_hashes.lock().expect("block hashes lock poisoned").get(&number).copied()
+    }
+}
+
+/// The fields of an `eth_getBlockByNumber` response needed to map its number to its hash.
+#[derive(Deserialize)]
+struct BlockNumberAndHash {
+    number: U64,
+    hash: B256,
+}
+
+impl<S> Service<RequestPacket> for DiskCacheService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let requests = match &request {
+            RequestPacket::Single(request) => std::slice::from_ref(request),
+            RequestPacket::Batch(requests) => requests.as_slice(),
+        };
+        let paths = requests
+            .iter()
+            .map(|request| self.cache_path(request).map(|path| (request.id().clone(), path)))
+            .collect::<Option<Vec<_>>>();
+
+        // Only requests where every call can be cached are served from disk, to keep batches
+        // intact.
+        let cached = paths.as_ref().and_then(|paths| {
+            paths
+                .iter()
+                .map(|(id, path)| {
+                    let payload = read_cached(path)?;
+                    Some(Response { id: id.clone(), payload: ResponsePayload::Success(payload) })
+                })
+                .collect::<Option<Vec<_>>>()
+        });
+        if let Some(mut responses) = cached {
+            tracing::debug!("serving {} responses from the disk cache", responses.len());
+            let response = match request {
+                RequestPacket::Single(_) => ResponsePacket::Single(responses.remove(0)),
+                RequestPacket::Batch(_) => ResponsePacket::Batch(responses),
+            };
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let block_lookups = requests
+            .iter()
+            .filter(|request| request.method() == "eth_getBlockByNumber")
+            .map(|request| request.id().clone())
+            .collect::<Vec<Id>>();
+        let block_hashes = self.block_hashes.clone();
+        let mut inner = self.inner.clone();
+        std::mem::swap(&mut self.inner, &mut inner);
+        Box::pin(async move {
+            let response = inner.call(request).await?;
+            let responses = match &response {
+                ResponsePacket::Single(response) => std::slice::from_ref(response),
+                ResponsePacket::Batch(responses) => responses.as_slice(),
+            };
+
+            for response in responses {
+                let ResponsePayload::Success(payload) = &response.payload else { continue };
+                if block_lookups.contains(&response.id) {
+                    if let Ok(block) = serde_json::from_str::<BlockNumberAndHash>(payload.get()) {
+                        let mut block_hashes =
+                            block_hashes.lock().expect("block hashes lock poisoned");
+                        block_hashes.insert(block.number.to(), block.hash);
+                    }
+                    continue;
+                }
+                // A null result means the block or account doesn't exist yet, which may change.
+                if payload.get() == "null" {
+                    continue;
+                }
+                let Some((_, path)) = paths.iter().flatten().find(|(id, _)| *id == response.id)
+                else {
+                    continue;
+                };
+                if let Err(err) = write_cached(path, payload) {
+                    tracing::warn!("failed to write {} to the disk cache: {}", path.display(), err);
+                }
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Reads a cached response payload, if there is a valid one at `path`.
+fn read_cached(path: &Path) -> Option<Box<RawValue>> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    RawValue::from_string(contents).ok()
+}
+
+/// Writes a response payload to `path`, creating the cache directory if needed.
+fn write_cached(path: &Path, payload: &RawValue) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, payload.get())
+}
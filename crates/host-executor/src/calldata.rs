@@ -0,0 +1,61 @@
+use std::path::Path;
+
+use alloy_dyn_abi::{JsonAbiExt, Specifier};
+use alloy_json_abi::{Function, JsonAbi};
+use alloy_primitives::{hex, Bytes};
+use eyre::{bail, eyre, OptionExt};
+use serde::Deserialize;
+
+/// Encodes calldata the way `cast calldata` does.
+///
+/// `input` is either raw hex calldata, in which case `args` must be empty, or a function
+/// signature such as `balanceOf(address)` whose arguments are parsed from `args`.
+pub fn encode_calldata(input: &str, args: &[&str]) -> eyre::Result<Bytes> {
+    if !input.contains('(') {
+        if !args.is_empty() {
+            bail!("arguments can't be passed with raw calldata");
+        }
+        return Ok(hex::decode(input)?.into());
+    }
+
+    let function = Function::parse(input).map_err(|err| eyre!("invalid signature: {err}"))?;
+    encode_function_call(&function, args)
+}
+
+/// Encodes a call to `function`, parsing each argument from its string representation.
+pub fn encode_function_call(function: &Function, args: &[&str]) -> eyre::Result<Bytes> {
+    if function.inputs.len() != args.len() {
+        bail!(
+            "{} expects {} arguments, got {}",
+            function.signature(),
+            function.inputs.len(),
+            args.len()
+        );
+    }
+
+    let values = function
+        .inputs
+        .iter()
+        .zip(args)
+        .map(|(param, arg)| Ok(param.resolve()?.coerce_str(arg)?))
+        .collect::<eyre::Result<Vec<_>>>()?;
+
+    Ok(function.abi_encode_input(&values)?.into())
+}
+
+/// Reads the function named `name` from a Foundry build artifact, such as `out/Foo.sol/Foo.json`.
+///
+/// Fails if the function is overloaded; use [`Function::parse`] with the full signature then.
+pub fn function_from_artifact(path: impl AsRef<Path>, name: &str) -> eyre::Result<Function> {
+    #[derive(Deserialize)]
+    struct Artifact {
+        abi: JsonAbi,
+    }
+
+    let artifact: Artifact = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+    let functions = artifact.abi.function(name).ok_or_eyre("function not found in artifact")?;
+    match functions.as_slice() {
+        [function] => Ok(function.clone()),
+        _ => bail!("{name} is overloaded, specify its full signature"),
+    }
+}
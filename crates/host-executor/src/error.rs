@@ -0,0 +1,63 @@
+use reth_primitives::Bytes;
+use revm_primitives::HaltReason;
+
+/// The ways a call made through the [`HostExecutor`](crate::HostExecutor) can fail.
+///
+/// [`HostError::Revert`], [`HostError::Halt`] and [`HostError::Decode`] are caused by the call
+/// itself, while [`HostError::ArchiveNodeRequired`] and [`HostError::Infrastructure`] cover
+/// failures to fetch or prepare the state.
+#[derive(Debug, thiserror::Error)]
+pub enum HostError {
+    /// The call reverted, with the given revert data.
+    #[error("call reverted: {0}")]
+    Revert(Bytes),
+    /// The call halted, e.g. by running out of gas or hitting an invalid opcode.
+    #[error("call halted: {0:?}")]
+    Halt(HaltReason),
+    /// The call succeeded, but its output couldn't be decoded as the expected return type.
+    #[error("failed to decode the call output: {0}")]
+    Decode(#[from] alloy_sol_types::Error),
+    /// The provider no longer has the state at `block`, usually because it isn't an archive node.
+    #[error("the provider has pruned the state at block {block}, use an archive node")]
+    ArchiveNodeRequired {
+        /// The block whose state was requested.
+        block: u64,
+    },
+    /// A call read a block hash further back than the configured maximum ancestor depth.
+    #[error("a call read the hash of a block {depth} blocks back, the maximum is {max}")]
+    AncestorDepthExceeded {
+        /// How many blocks back the oldest hash read is.
+        depth: u64,
+        /// The configured maximum depth.
+        max: u64,
+    },
+    /// The call couldn't be executed, e.g. because of an RPC failure.
+    #[error("{0}")]
+    Infrastructure(eyre::Report),
+}
+
+impl From<eyre::Report> for HostError {
+    fn from(err: eyre::Report) -> Self {
+        err.downcast().unwrap_or_else(Self::Infrastructure)
+    }
+}
+
+/// Messages nodes return when asked for state they have pruned.
+const PRUNED_STATE_MESSAGES: [&str; 4] =
+    ["missing trie node", "historical state", "state is not available", "state not available"];
+
+/// Replaces `err` with [`HostError::ArchiveNodeRequired`] if it was caused by the provider having
+/// pruned the state at `block`.
+pub(crate) fn check_pruned_state(err: impl Into<eyre::Report>, block: u64) -> eyre::Report {
+    let err = err.into();
+    let pruned = err.chain().any(|cause| {
+        let message = cause.to_string();
+        PRUNED_STATE_MESSAGES.iter().any(|pruned| message.contains(pruned))
+    });
+
+    if pruned {
+        HostError::ArchiveNodeRequired { block }.into()
+    } else {
+        err
+    }
+}
@@ -0,0 +1,59 @@
+use std::task::{Context, Poll};
+
+use alloy_json_rpc::{RequestPacket, ResponsePacket};
+use alloy_transport::{TransportError, TransportErrorKind, TransportFut};
+use tower::{Service, ServiceExt};
+
+/// A transport that sends each request to the first of several endpoints that answers.
+///
+/// Requests go to the endpoints in order. If an endpoint fails at the transport level (e.g. it's
+/// unreachable or returns an HTTP error), the request is retried on the next one. Errors returned
+/// by the JSON-RPC server itself are passed through unchanged, since other endpoints would return
+/// them too.
+#[derive(Debug, Clone)]
+pub struct FailoverService<S> {
+    transports: Vec<S>,
+}
+
+impl<S> FailoverService<S> {
+    /// Creates a new [`FailoverService`] over `transports`, in order of preference.
+    pub fn new(transports: impl IntoIterator<Item = S>) -> Self {
+        Self { transports: transports.into_iter().collect() }
+    }
+}
+
+impl<S> Service<RequestPacket> for FailoverService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Readiness is checked per endpoint when the request is sent.
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let transports = self.transports.clone();
+        Box::pin(async move {
+            let mut last_error = None;
+            for (index, transport) in transports.into_iter().enumerate() {
+                match transport.oneshot(request.clone()).await {
+                    Ok(response) => return Ok(response),
+                    Err(err) => {
+                        tracing::warn!("endpoint {} failed, trying the next one: {}", index, err);
+                        last_error = Some(err);
+                    }
+                }
+            }
+            Err(last_error
+                .unwrap_or_else(|| TransportErrorKind::custom_str("no endpoints configured")))
+        })
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use alloy_json_rpc::{RequestPacket, Response, ResponsePacket, ResponsePayload, SerializedRequest};
+use alloy_transport::{TransportError, TransportErrorKind, TransportFut};
+use eyre::WrapErr;
+use serde_json::value::RawValue;
+use tower::{Layer, Service};
+
+/// The responses in a fixture file, keyed by [`fixture_key`].
+type Fixture = BTreeMap<String, Box<RawValue>>;
+
+/// A [`Layer`] that records every successful JSON-RPC response into a fixture file.
+///
+/// Unlike [`crate::DiskCacheLayer`], every request is recorded, including `eth_chainId` and
+/// requests for tags such as `latest`, so a [`ReplayService`] can later build the same sketch
+/// without any endpoint. The file is rewritten after each response, and holds the responses of
+/// all the services created by this layer.
+#[derive(Debug, Clone)]
+pub struct RecordLayer {
+    path: PathBuf,
+    fixture: Arc<Mutex<Fixture>>,
+}
+
+impl RecordLayer {
+    /// Creates a new [`RecordLayer`] writing to `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), fixture: Default::default() }
+    }
+}
+
+impl<S> Layer<S> for RecordLayer {
+    type Service = RecordService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RecordService { inner, path: self.path.clone(), fixture: self.fixture.clone() }
+    }
+}
+
+/// The service created by a [`RecordLayer`].
+#[derive(Debug, Clone)]
+pub struct RecordService<S> {
+    inner: S,
+    path: PathBuf,
+    fixture: Arc<Mutex<Fixture>>,
+}
+
+impl<S> Service<RequestPacket> for RecordService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let keys = requests(&request)
+            .iter()
+            .map(|request| (request.id().clone(), fixture_key(request)))
+            .collect::<Vec<_>>();
+        let path = self.path.clone();
+        let fixture = self.fixture.clone();
+        let mut inner = self.inner.clone();
+        std::mem::swap(&mut self.inner, &mut inner);
+        Box::pin(async move {
+            let response = inner.call(request).await?;
+            let responses = match &response {
+                ResponsePacket::Single(response) => std::slice::from_ref(response),
+                ResponsePacket::Batch(responses) => responses.as_slice(),
+            };
+
+            let mut fixture = fixture.lock().expect("fixture lock poisoned");
+            for response in responses {
+                let ResponsePayload::Success(payload) = &response.payload else { continue };
+                if let Some((_, key)) = keys.iter().find(|(id, _)| *id == response.id) {
+                    fixture.insert(key.clone(), payload.clone());
+                }
+            }
+            if let Err(err) = write_fixture(&path, &fixture) {
+                tracing::warn!("failed to write the fixture {}: {}", path.display(), err);
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// A transport that answers requests from a fixture file written by a [`RecordLayer`].
+///
+/// Requests that weren't recorded fail, so a replayed run never silently reaches the network.
+#[derive(Debug, Clone)]
+pub struct ReplayService {
+    fixture: Arc<Fixture>,
+}
+
+impl ReplayService {
+    /// Loads the fixture at `path`.
+    pub fn new(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read the fixture {}", path.display()))?;
+        let fixture = serde_json::from_str(&contents)
+            .wrap_err_with(|| format!("failed to parse the fixture {}", path.display()))?;
+        Ok(Self { fixture: Arc::new(fixture) })
+    }
+}
+
+impl Service<RequestPacket> for ReplayService {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let responses = requests(&request)
+            .iter()
+            .map(|request| {
+                let key = fixture_key(request);
+                let payload = self.fixture.get(&key).cloned().ok_or_else(|| {
+                    TransportErrorKind::custom_str(&format!("request not in the fixture: {key}"))
+                })?;
+                Ok(Response {
+                    id: request.id().clone(),
+                    payload: ResponsePayload::Success(payload),
+                })
+            })
+            .collect::<Result<Vec<_>, TransportError>>();
+
+        Box::pin(async move {
+            let mut responses = responses?;
+            Ok(match request {
+                RequestPacket::Single(_) => ResponsePacket::Single(responses.remove(0)),
+                RequestPacket::Batch(_) => ResponsePacket::Batch(responses),
+            })
+        })
+    }
+}
+
+fn requests(packet: &RequestPacket) -> &[SerializedRequest] {
+    match packet {
+        RequestPacket::Single(request) => std::slice::from_ref(request),
+        RequestPacket::Batch(requests) => requests.as_slice(),
+    }
+}
+
+/// Returns the key of `request` in a fixture. Request ids aren't part of it, since they depend on
+/// how many requests were sent before.
+fn fixture_key(request: &SerializedRequest) -> String {
+    let params = request.params().map(RawValue::get).unwrap_or_default();
+    format!("{} {}", request.method(), params)
+}
+
+/// Writes `fixture` to `path`, creating its directory if needed.
+fn write_fixture(path: &Path, fixture: &Fixture) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(fixture)?)
+}
@@ -1,20 +1,77 @@
 #[cfg(test)]
 mod test;
 
-use std::collections::BTreeSet;
+pub mod calldata;
 
+mod cache;
+pub use cache::{DiskCacheLayer, DiskCacheService};
+
+mod error;
+use error::check_pruned_state;
+pub use error::HostError;
+
+mod failover;
+pub use failover::FailoverService;
+
+mod fixture;
+pub use fixture::{RecordLayer, RecordService, ReplayService};
+
+mod metrics;
+pub use metrics::{MetricsLayer, MetricsService, RpcCounts, RpcMetrics};
+
+mod provider;
+pub use provider::{
+    replay_provider, CachedRetryProvider, FailoverRetryProvider, MeteredRetryProvider,
+    RecordingRetryProvider, ReplayProvider, RetryConfig, RetryProvider,
+};
+
+mod rate_limit;
+pub use rate_limit::{RateLimitLayer, RateLimitService};
+
+mod snapshot;
+pub use snapshot::HostExecutorSnapshot;
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+
+mod verifier;
+pub use verifier::verifier_contract;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use alloy_json_rpc::{RpcParam, RpcReturn};
 use alloy_provider::{network::AnyNetwork, Provider};
-use alloy_rpc_types::{BlockId, BlockNumberOrTag, BlockTransactionsKind};
-use alloy_transport::Transport;
-use eyre::{eyre, OptionExt};
+use alloy_rpc_client::BatchRequest;
+use alloy_rpc_types::{
+    BlockId, BlockNumberOrTag, BlockTransactionsKind, EIP1186AccountProofResponse, Transaction,
+    TransactionInput, TransactionRequest, WithOtherFields,
+};
+use alloy_sol_types::SolCall;
+use alloy_transport::{RpcError, Transport};
+use eyre::eyre;
+use reth_chainspec::ChainSpec;
 use reth_primitives::{Block, Bytes, Header};
-use revm::db::CacheDB;
-use revm_primitives::{B256, U256};
+use revm::{db::CacheDB, DatabaseRef};
+use revm_primitives::{AccountInfo, Address, Bytecode, ExecutionResult, B256, KECCAK_EMPTY, U256};
 use rsp_mpt::EthereumState;
 use rsp_primitives::account_proof::eip1186_proof_to_account_proof;
 use rsp_rpc_db::RpcDb;
+use serde::Deserialize;
+
+use sp1_cc_client_executor::{
+    check_fork_supported, genesis::Genesis, io::EVMStateSketch, new_evm, ContractCalldata,
+    ContractInput,
+};
+
+/// The maximum number of calls sent in a single JSON-RPC batch. Most providers reject larger
+/// batches.
+pub const MAX_BATCH_SIZE: usize = 100;
+
+/// How many blocks back the BLOCKHASH opcode can read.
+pub const BLOCK_HASH_HISTORY: u64 = 256;
 
-use sp1_cc_client_executor::{io::EVMStateSketch, new_evm, ContractInput};
+/// The witness size above which [`HostExecutor::finalize`] warns about the [`EVMStateSketch`].
+pub const WITNESS_SIZE_WARNING_BYTES: u64 = 10 * 1024 * 1024;
 
 /// An executor that fetches data from a [`Provider`].
 ///
@@ -22,12 +79,20 @@ use sp1_cc_client_executor::{io::EVMStateSketch, new_evm, ContractInput};
 /// [`EVMStateSketch`].
 #[derive(Debug, Clone)]
 pub struct HostExecutor<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> {
+    /// The chain the block belongs to.
+    pub genesis: Genesis,
     /// The header of the block to execute our view functions on.
     pub header: Header,
     /// The [`RpcDb`] used to back the EVM.
     pub rpc_db: RpcDb<T, P>,
     /// The provider used to fetch data.
     pub provider: P,
+    /// Accounts and storage slots to include in the [`EVMStateSketch`] even if no call touches
+    /// them.
+    pub included_state: BTreeMap<Address, BTreeSet<U256>>,
+    /// How many blocks back calls may read with the BLOCKHASH opcode. Only the headers calls
+    /// actually read back to are included in the [`EVMStateSketch`], up to this depth.
+    pub max_ancestor_depth: u64,
 }
 
 impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> HostExecutor<T, P> {
@@ -36,11 +101,9 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> HostExecutor<T, P
         let block = provider
             .get_block_by_number(block_number, true)
             .await?
-            .map(|block| Block::try_from(block.inner))
-            .ok_or(eyre!("couldn't fetch block: {}", block_number))??;
+            .ok_or(eyre!("couldn't fetch block: {}", block_number))?;
 
-        let rpc_db = RpcDb::new(provider.clone(), block.header.number);
-        Ok(Self { header: block.header, rpc_db, provider })
+        Self::from_rpc_block(provider, block.inner)
     }
 
     /// Create a new [`HostExecutor`] with a specific [`Provider`] and [`BlockId`].
@@ -48,43 +111,381 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> HostExecutor<T, P
         let block = provider
             .get_block(block_identifier, BlockTransactionsKind::Full)
             .await?
-            .map(|block| Block::try_from(block.inner))
-            .ok_or(eyre!("couldn't fetch block: {}", block_identifier))??;
+            .ok_or(eyre!("couldn't fetch block: {}", block_identifier))?;
+
+        Self::from_rpc_block(provider, block.inner)
+    }
 
+    /// Create a new [`HostExecutor`] from a block returned by the provider.
+    fn from_rpc_block(
+        provider: P,
+        block: alloy_rpc_types::Block<WithOtherFields<Transaction>>,
+    ) -> eyre::Result<Self> {
+        let block = Block::try_from(block)?;
         let rpc_db = RpcDb::new(provider.clone(), block.header.number);
-        Ok(Self { header: block.header, rpc_db, provider })
+        Ok(Self {
+            genesis: Genesis::Mainnet,
+            header: block.header,
+            rpc_db,
+            provider,
+            included_state: BTreeMap::new(),
+            max_ancestor_depth: BLOCK_HASH_HISTORY,
+        })
+    }
+
+    /// Sets the chain the block belongs to. Defaults to [`Genesis::Mainnet`].
+    ///
+    /// The provider's chain id is checked against it when finalizing, or explicitly with
+    /// [`HostExecutor::check_chain_id`].
+    pub fn with_genesis(mut self, genesis: Genesis) -> Self {
+        self.genesis = genesis;
+        self
+    }
+
+    /// Sets how many blocks back calls may read with the BLOCKHASH opcode. Defaults to, and is
+    /// capped at, the [`BLOCK_HASH_HISTORY`] blocks the EVM allows.
+    ///
+    /// Each of those blocks adds a header to the [`EVMStateSketch`], so a lower depth bounds the
+    /// witness size. Calls reading further back fail with [`HostError::AncestorDepthExceeded`].
+    pub fn with_max_ancestor_depth(mut self, max_ancestor_depth: u64) -> Self {
+        self.max_ancestor_depth = max_ancestor_depth.min(BLOCK_HASH_HISTORY);
+        self
+    }
+
+    /// Sets the genesis from the provider's chain id.
+    ///
+    /// Returns an error listing the supported chains if the chain id doesn't match any of the
+    /// built-in [`Genesis`] variants. Custom chains have to be set with
+    /// [`HostExecutor::with_genesis`].
+    pub async fn detect_genesis(self) -> eyre::Result<Self> {
+        let chain_id = self.provider.get_chain_id().await?;
+        let genesis = Genesis::from_chain_id(chain_id).ok_or_else(|| {
+            let supported = Genesis::KNOWN
+                .iter()
+                .map(|genesis| format!("{:?} ({})", genesis, genesis.chain_id()))
+                .collect::<Vec<_>>();
+            eyre!("unknown chain id {}, supported chains: {}", chain_id, supported.join(", "))
+        })?;
+
+        Ok(self.with_genesis(genesis))
+    }
+
+    /// Prefetches the state accessed by `call` using `eth_createAccessList`.
+    ///
+    /// The accounts and storage slots returned by the provider are fetched with batched
+    /// `eth_getProof` and `eth_getCode` requests and loaded into the [`RpcDb`] up front, so that
+    /// [`HostExecutor::execute`] doesn't have to discover them one miss at a time.
+    #[tracing::instrument(
+        skip_all,
+        fields(block = self.header.number, contract = %call.contract_address)
+    )]
+    pub async fn prefetch_access_list(&self, call: &ContractInput) -> eyre::Result<()> {
+        let result = self
+            .provider
+            .create_access_list(&WithOtherFields::new(call_request(call)))
+            .block_id(self.header.number.into())
+            .await?;
+        if let Some(error) = result.error {
+            tracing::warn!("access list generation reported an error: {}", error);
+        }
+
+        tracing::info!("prefetching {} accounts from access list", result.access_list.0.len());
+        let block = BlockId::number(self.header.number);
+        let requests = result
+            .access_list
+            .0
+            .into_iter()
+            .map(|item| (item.address, item.storage_keys, block))
+            .collect::<Vec<_>>();
+
+        for chunk in requests.chunks(MAX_BATCH_SIZE) {
+            let proofs: Vec<EIP1186AccountProofResponse> =
+                self.batch_call("eth_getProof", chunk).await?;
+
+            let with_code = proofs
+                .iter()
+                .filter(|proof| proof.code_hash != KECCAK_EMPTY && !proof.code_hash.is_zero())
+                .map(|proof| (proof.address, block))
+                .collect::<Vec<_>>();
+            let mut codes = HashMap::new();
+            if !with_code.is_empty() {
+                let responses: Vec<Bytes> = self.batch_call("eth_getCode", &with_code).await?;
+                codes.extend(with_code.iter().map(|(address, _)| *address).zip(responses));
+            }
+
+            for ((address, keys, _), proof) in chunk.iter().zip(proofs) {
+                let storage = keys
+                    .iter()
+                    .map(|key| U256::from_be_bytes(key.0))
+                    .zip(proof.storage_proof.iter().map(|slot| slot.value));
+                self.insert_account(
+                    *address,
+                    proof.balance,
+                    proof.nonce,
+                    codes.remove(address).unwrap_or_default(),
+                    storage,
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prefetches the state accessed by `call` using `debug_traceCall` with the prestate tracer.
+    ///
+    /// This captures exactly the state touched by the call in a single request, and loads it into
+    /// the [`RpcDb`] without any further requests. Returns `false`
+    /// if the provider doesn't support the method, in which case the state is fetched
+    /// incrementally by the [`RpcDb`] during [`HostExecutor::execute`] instead.
+    #[tracing::instrument(
+        skip_all,
+        fields(block = self.header.number, contract = %call.contract_address)
+    )]
+    pub async fn prefetch_prestate_trace(&self, call: &ContractInput) -> eyre::Result<bool> {
+        let params = (
+            call_request(call),
+            BlockId::number(self.header.number),
+            serde_json::json!({ "tracer": "prestateTracer" }),
+        );
+        let prestate = match self
+            .provider
+            .raw_request::<_, BTreeMap<Address, PrestateAccount>>("debug_traceCall".into(), params)
+            .await
+        {
+            Ok(prestate) => prestate,
+            Err(RpcError::ErrorResp(error)) => {
+                tracing::warn!("debug_traceCall unavailable, falling back to the RpcDb: {}", error);
+                return Ok(false);
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        tracing::info!("prefetching {} accounts from prestate trace", prestate.len());
+        for (address, account) in prestate {
+            let storage = account
+                .storage
+                .into_iter()
+                .map(|(key, value)| (U256::from_be_bytes(key.0), U256::from_be_bytes(value.0)));
+            self.insert_account(address, account.balance, account.nonce, account.code, storage);
+        }
+
+        Ok(true)
+    }
+
+    /// Loads an account and some of its storage slots into the [`RpcDb`], as if it had fetched
+    /// them itself. Slots the [`RpcDb`] already holds are overwritten.
+    fn insert_account(
+        &self,
+        address: Address,
+        balance: U256,
+        nonce: u64,
+        code: Bytes,
+        storage: impl IntoIterator<Item = (U256, U256)>,
+    ) {
+        let code = Bytecode::new_raw(code);
+        let info = AccountInfo { balance, nonce, code_hash: code.hash_slow(), code: Some(code) };
+        self.rpc_db.accounts.borrow_mut().insert(address, info);
+        self.rpc_db.storage.borrow_mut().entry(address).or_default().extend(storage);
+    }
+
+    /// Prefetches an account, so that it's part of the [`EVMStateSketch`] even if no call
+    /// executed on the host touches it.
+    ///
+    /// This is useful for state that the client program reads directly from the `witness_db`, or
+    /// through calls that are only made in the client.
+    pub async fn prefetch_account(&self, address: Address) -> eyre::Result<Option<AccountInfo>> {
+        Ok(self.rpc_db.basic_ref(address)?)
+    }
+
+    /// Prefetches an account and the given storage slots, returning the value of each slot.
+    pub async fn prefetch_storage(
+        &self,
+        address: Address,
+        slots: impl IntoIterator<Item = U256>,
+    ) -> eyre::Result<Vec<U256>> {
+        self.prefetch_account(address).await?;
+        let mut values = Vec::new();
+        for slot in slots {
+            values.push(self.rpc_db.storage_ref(address, slot)?);
+        }
+        Ok(values)
+    }
+
+    /// Prefetches the bytecode of an account. The code is fetched together with the account, so
+    /// this is equivalent to [`HostExecutor::prefetch_account`].
+    pub async fn prefetch_code(&self, address: Address) -> eyre::Result<Bytecode> {
+        let account = self.prefetch_account(address).await?;
+        Ok(account.and_then(|account| account.code).unwrap_or_default())
+    }
+
+    /// Registers an account and some of its storage slots to be included in the
+    /// [`EVMStateSketch`], whether or not a call executed on the host touches them.
+    ///
+    /// Unlike [`HostExecutor::prefetch_storage`], nothing is fetched until
+    /// [`HostExecutor::finalize`]. Use this for state the client program may branch on, so it
+    /// doesn't fail on missing trie nodes at proving time.
+    pub fn include_storage(&mut self, address: Address, slots: impl IntoIterator<Item = U256>) {
+        self.included_state.entry(address).or_default().extend(slots);
+    }
+
+    /// Registers an account to be included in the [`EVMStateSketch`]. See
+    /// [`HostExecutor::include_storage`].
+    pub fn include_account(&mut self, address: Address) {
+        self.include_storage(address, []);
     }
 
     /// Executes the smart contract call with the given [`ContractInput`].
-    pub async fn execute(&mut self, call: ContractInput) -> eyre::Result<Bytes> {
-        let cache_db = CacheDB::new(&self.rpc_db);
-        let mut evm = new_evm(cache_db, &self.header, U256::ZERO, &call);
-        let output = evm.transact()?;
-        let output_bytes = output.result.output().ok_or_eyre("Error getting result")?;
+    ///
+    /// Reverts and halts are returned as [`HostError::Revert`] and [`HostError::Halt`].
+    pub async fn execute(&mut self, call: ContractInput) -> Result<Bytes, HostError> {
+        match self.execute_with_result(call).await? {
+            ExecutionResult::Success { output, .. } => Ok(output.into_data()),
+            ExecutionResult::Revert { output, .. } => Err(HostError::Revert(output)),
+            ExecutionResult::Halt { reason, .. } => Err(HostError::Halt(reason)),
+        }
+    }
+
+    /// Calls `call` on the contract at `contract_address`, decoding its return value.
+    pub async fn call<C: SolCall>(
+        &mut self,
+        contract_address: Address,
+        caller_address: Address,
+        call: C,
+    ) -> Result<C::Return, HostError> {
+        let output =
+            self.execute(ContractInput::new_call(contract_address, caller_address, call)).await?;
+
+        Ok(C::abi_decode_returns(&output, true)?)
+    }
+
+    /// Executes the smart contract call with the given [`ContractInput`], returning the full
+    /// [`ExecutionResult`].
+    ///
+    /// Unlike [`HostExecutor::execute`], this exposes the gas used, the logs, and whether the call
+    /// succeeded, reverted or halted, so they can be checked before paying for a proof.
+    ///
+    /// Reverts and halts are part of the [`ExecutionResult`]. Errors are failures to run the call
+    /// at all, such as [`HostError::ArchiveNodeRequired`] if the provider has pruned the state at
+    /// the block.
+    #[tracing::instrument(
+        skip_all,
+        fields(block = self.header.number, contract = %call.contract_address)
+    )]
+    pub async fn execute_with_result(
+        &mut self,
+        call: ContractInput,
+    ) -> Result<ExecutionResult, HostError> {
+        let block = self.header.number;
+        let mut cache_db = CacheDB::new(&self.rpc_db);
+        call.apply_overrides(&mut cache_db).map_err(|err| check_pruned_state(err, block))?;
+        let chain_spec = ChainSpec::try_from(&self.genesis)?;
+        check_fork_supported(&self.header, &chain_spec)?;
+        let mut evm = new_evm(cache_db, &self.header, &chain_spec, &call);
+        let oldest_ancestor = *self.rpc_db.oldest_ancestor.borrow();
+        let output = evm.transact();
+        if let Err(err) = self.check_ancestor_depth() {
+            // Forget the block hashes read by the rejected call, so that they don't fail later
+            // calls or add their headers to the sketch.
+            *self.rpc_db.oldest_ancestor.borrow_mut() = oldest_ancestor;
+            self.rpc_db.block_hashes.borrow_mut().retain(|number, _| *number >= oldest_ancestor);
+            return Err(err);
+        }
+        let output = output.map_err(|err| check_pruned_state(err, block))?;
+        tracing::info!(
+            gas_used = output.result.gas_used(),
+            success = output.result.is_success(),
+            "executed call"
+        );
 
-        Ok(output_bytes.clone())
+        Ok(output.result)
     }
 
     /// Returns the cumulative [`EVMStateSketch`] after executing some smart contracts.
+    ///
+    /// Progress is reported through `tracing`. Use [`HostExecutor::finalize_with_progress`] to
+    /// handle it directly.
     pub async fn finalize(&self) -> eyre::Result<EVMStateSketch> {
+        self.finalize_with_progress(|progress| match progress {
+            FinalizeProgress::StorageProof { fetched, total, proof_bytes, .. } => {
+                tracing::info!(
+                    "fetched storage proof {}/{} ({} bytes)",
+                    fetched,
+                    total,
+                    proof_bytes
+                )
+            }
+            FinalizeProgress::AncestorHeader { fetched, total } => {
+                tracing::info!("fetched ancestor header {}/{}", fetched, total)
+            }
+        })
+        .await
+    }
+
+    /// Returns the cumulative [`EVMStateSketch`] after executing some smart contracts, calling
+    /// `on_progress` after each proof and header is fetched.
+    ///
+    /// If the provider has pruned the state at the block, the error is a
+    /// [`HostError::ArchiveNodeRequired`].
+    #[tracing::instrument(skip_all, fields(block = self.header.number))]
+    pub async fn finalize_with_progress(
+        &self,
+        mut on_progress: impl FnMut(FinalizeProgress),
+    ) -> eyre::Result<EVMStateSketch> {
         let block_number = self.header.number;
 
+        // Make sure the provider serves the chain the sketch claims to be for, since a mismatch
+        // would otherwise only show up when the client validates the witness.
+        self.check_chain_id().await?;
+
+        // Make sure the block is still canonical, so the proofs below come from the same chain as
+        // the state that was executed against.
+        self.check_reorg().await?;
+
+        // Fetch the state that was registered explicitly, so that it's part of the requests.
+        for (address, slots) in self.included_state.iter() {
+            self.prefetch_storage(*address, slots.iter().copied()).await?;
+        }
+
         // For every account touched, fetch the storage proofs for all the slots touched.
         let state_requests = self.rpc_db.get_state_requests();
         tracing::info!("fetching storage proofs");
         let mut storage_proofs = Vec::new();
+        let mut proof_bytes = 0;
 
-        for (address, used_keys) in state_requests.iter() {
-            let keys = used_keys
-                .iter()
-                .map(|key| B256::from(*key))
-                .collect::<BTreeSet<_>>()
-                .into_iter()
-                .collect::<Vec<_>>();
+        let requests = state_requests
+            .iter()
+            .map(|(address, used_keys)| {
+                let keys = used_keys
+                    .iter()
+                    .map(|key| B256::from(*key))
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                (*address, keys, BlockId::number(block_number))
+            })
+            .collect::<Vec<_>>();
 
-            let storage_proof =
-                self.provider.get_proof(*address, keys).block_id(block_number.into()).await?;
-            storage_proofs.push(eip1186_proof_to_account_proof(storage_proof));
+        for chunk in requests.chunks(MAX_BATCH_SIZE) {
+            let responses: Vec<EIP1186AccountProofResponse> =
+                self.batch_call("eth_getProof", chunk).await?;
+
+            for storage_proof in responses {
+                let address = storage_proof.address;
+                let account_proof_bytes = storage_proof.account_proof.iter().map(|node| node.len());
+                let storage_proof_bytes = storage_proof
+                    .storage_proof
+                    .iter()
+                    .flat_map(|proof| proof.proof.iter().map(|node| node.len()));
+                proof_bytes += account_proof_bytes.chain(storage_proof_bytes).sum::<usize>();
+                storage_proofs.push(eip1186_proof_to_account_proof(storage_proof));
+
+                on_progress(FinalizeProgress::StorageProof {
+                    address,
+                    fetched: storage_proofs.len(),
+                    total: state_requests.len(),
+                    proof_bytes,
+                });
+            }
         }
 
         let storage_proofs_by_address =
@@ -92,20 +493,179 @@ impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> HostExecutor<T, P
         let state = EthereumState::from_proofs(self.header.state_root, &storage_proofs_by_address)?;
 
         // Fetch the parent headers needed to constrain the BLOCKHASH opcode.
-        let oldest_ancestor = *self.rpc_db.oldest_ancestor.borrow();
+        let oldest_ancestor = (*self.rpc_db.oldest_ancestor.borrow())
+            .max(block_number.saturating_sub(self.max_ancestor_depth));
         let mut ancestor_headers = vec![];
-        tracing::info!("fetching {} ancestor headers", block_number - oldest_ancestor);
-        for height in (oldest_ancestor..=(block_number - 1)).rev() {
-            let block = self.provider.get_block_by_number(height.into(), false).await?.unwrap();
-            ancestor_headers.push(block.inner.header.try_into()?);
+        let total_ancestors = (block_number - oldest_ancestor) as usize;
+        tracing::info!("fetching {} ancestor headers", total_ancestors);
+        let requests = (oldest_ancestor..=(block_number - 1))
+            .rev()
+            .map(|height| (BlockNumberOrTag::Number(height), false))
+            .collect::<Vec<_>>();
+        for chunk in requests.chunks(MAX_BATCH_SIZE) {
+            let blocks: Vec<Option<alloy_rpc_types::Block>> =
+                self.batch_call("eth_getBlockByNumber", chunk).await?;
+
+            for (block, (height, _)) in blocks.into_iter().zip(chunk) {
+                let block = block.ok_or(eyre!("couldn't fetch block: {}", height))?;
+                ancestor_headers.push(block.header.try_into()?);
+
+                on_progress(FinalizeProgress::AncestorHeader {
+                    fetched: ancestor_headers.len(),
+                    total: total_ancestors,
+                });
+            }
         }
 
-        Ok(EVMStateSketch {
+        let sketch = EVMStateSketch {
+            genesis: self.genesis.clone(),
             header: self.header.clone(),
             ancestor_headers,
             state,
             state_requests,
             bytecodes: self.rpc_db.get_bytecodes(),
-        })
+        };
+
+        let stats = sketch.stats()?;
+        tracing::info!(
+            accounts = stats.accounts,
+            storage_slots = stats.storage_slots,
+            bytecodes = stats.bytecodes,
+            headers = stats.headers,
+            proof_bytes,
+            witness_bytes = stats.total_bytes,
+            "finalized sketch"
+        );
+        if stats.total_bytes > WITNESS_SIZE_WARNING_BYTES {
+            tracing::warn!(
+                "sketch is {} bytes, which will be expensive to prove: {:?}",
+                stats.total_bytes,
+                stats
+            );
+        }
+
+        // Check again, in case a reorg happened while the proofs were being fetched.
+        self.check_reorg().await?;
+
+        Ok(sketch)
+    }
+
+    /// Returns an error if the calls so far read a block hash further back than
+    /// [`HostExecutor::max_ancestor_depth`].
+    fn check_ancestor_depth(&self) -> Result<(), HostError> {
+        let depth = self.header.number - *self.rpc_db.oldest_ancestor.borrow();
+        if depth > self.max_ancestor_depth {
+            return Err(HostError::AncestorDepthExceeded { depth, max: self.max_ancestor_depth });
+        }
+
+        Ok(())
     }
+
+    /// Sends a JSON-RPC batch calling `method` once for each of `params`, returning the responses
+    /// in the same order.
+    async fn batch_call<Params: RpcParam, Resp: RpcReturn>(
+        &self,
+        method: &'static str,
+        params: &[Params],
+    ) -> eyre::Result<Vec<Resp>> {
+        let mut batch = BatchRequest::new(self.provider.client());
+        let waiters = params
+            .iter()
+            .map(|params| batch.add_call(method, params))
+            .collect::<Result<Vec<_>, _>>()?;
+        let block = self.header.number;
+        batch.send().await.map_err(|err| check_pruned_state(err, block))?;
+
+        let mut responses = Vec::with_capacity(waiters.len());
+        for waiter in waiters {
+            responses.push(waiter.await.map_err(|err| check_pruned_state(err, block))?);
+        }
+        Ok(responses)
+    }
+
+    /// Returns an error if the provider's chain id doesn't match the configured [`Genesis`].
+    pub async fn check_chain_id(&self) -> eyre::Result<()> {
+        let chain_id = self.provider.get_chain_id().await?;
+        if chain_id != self.genesis.chain_id() {
+            return Err(eyre!(
+                "provider chain id {} doesn't match the genesis chain id {}",
+                chain_id,
+                self.genesis.chain_id()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the block at `header.number` is no longer the one the executor was
+    /// created with, e.g. because it was reorged out since the executor was built on `Latest`.
+    ///
+    /// The block is fetched with `eth_getBlockByNumber`, which the [`DiskCacheLayer`] never
+    /// caches, so this always checks against the provider's current chain.
+    pub async fn check_reorg(&self) -> eyre::Result<()> {
+        let expected = self.header.hash_slow();
+        let block = self
+            .provider
+            .get_block_by_number(self.header.number.into(), false)
+            .await?
+            .ok_or(eyre!("couldn't fetch block: {}", self.header.number))?;
+        let actual = Header::try_from(block.inner.header)?.hash_slow();
+
+        if actual != expected {
+            return Err(eyre!(
+                "block {} was reorged: expected hash {}, got {}",
+                self.header.number,
+                expected,
+                actual
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A progress update emitted by [`HostExecutor::finalize_with_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinalizeProgress {
+    /// The storage proof for an account was fetched.
+    StorageProof {
+        /// The account whose proof was fetched.
+        address: Address,
+        /// The number of proofs fetched so far.
+        fetched: usize,
+        /// The total number of proofs to fetch.
+        total: usize,
+        /// The total size of the proof nodes fetched so far, in bytes.
+        proof_bytes: usize,
+    },
+    /// An ancestor header was fetched, to support the BLOCKHASH opcode.
+    AncestorHeader {
+        /// The number of headers fetched so far.
+        fetched: usize,
+        /// The total number of headers to fetch.
+        total: usize,
+    },
+}
+
+/// Builds the [`TransactionRequest`] corresponding to a [`ContractInput`].
+fn call_request(call: &ContractInput) -> TransactionRequest {
+    let request = TransactionRequest::default()
+        .from(call.caller_address)
+        .input(TransactionInput::new(call.calldata.to_bytes()));
+    match call.calldata {
+        ContractCalldata::Call(_) => request.to(call.contract_address),
+        ContractCalldata::Create(_) => request,
+    }
+}
+
+/// An account in the output of the `prestateTracer`. The tracer omits fields that are zero or
+/// empty.
+#[derive(Debug, Deserialize)]
+struct PrestateAccount {
+    #[serde(default)]
+    balance: U256,
+    #[serde(default)]
+    nonce: u64,
+    #[serde(default)]
+    code: Bytes,
+    #[serde(default)]
+    storage: BTreeMap<B256, B256>,
 }
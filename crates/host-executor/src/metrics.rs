@@ -0,0 +1,166 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use alloy_json_rpc::{Id, RequestPacket, ResponsePacket, ResponsePayload, SerializedRequest};
+use alloy_transport::{TransportError, TransportFut};
+use serde_json::{value::RawValue, Value};
+use tower::{Layer, Service};
+
+/// Counters of the state fetched through a [`MetricsLayer`], shared by all its clones.
+///
+/// Every request the layer sees is counted, including retries, so the counts reflect the load on
+/// the endpoint rather than the size of the resulting sketch. See
+/// [`sp1_cc_client_executor::io::EVMStateSketch::stats`] for the latter.
+#[derive(Debug, Clone, Default)]
+pub struct RpcMetrics {
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    accounts: AtomicU64,
+    slots: AtomicU64,
+    proofs: AtomicU64,
+    bytes: AtomicU64,
+    errors: AtomicU64,
+}
+
+/// A snapshot of [`RpcMetrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RpcCounts {
+    /// The accounts fetched with `eth_getProof`.
+    pub accounts_fetched: u64,
+    /// The storage slots fetched, with `eth_getStorageAt` or as keys of `eth_getProof`.
+    pub slots_fetched: u64,
+    /// The account and storage proofs in successful `eth_getProof` responses.
+    pub proofs_fetched: u64,
+    /// The size of the successful responses, in bytes of JSON.
+    pub bytes_downloaded: u64,
+    /// The error responses, plus one per call of a request that failed at the transport level.
+    pub rpc_errors: u64,
+}
+
+impl RpcMetrics {
+    /// Returns the current counts.
+    pub fn counts(&self) -> RpcCounts {
+        let counters = &self.counters;
+        RpcCounts {
+            accounts_fetched: counters.accounts.load(Ordering::Relaxed),
+            slots_fetched: counters.slots.load(Ordering::Relaxed),
+            proofs_fetched: counters.proofs.load(Ordering::Relaxed),
+            bytes_downloaded: counters.bytes.load(Ordering::Relaxed),
+            rpc_errors: counters.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A [`Layer`] counting the accounts, slots and proofs fetched, the bytes downloaded and the
+/// errors returned into a [`RpcMetrics`].
+#[derive(Debug, Clone)]
+pub struct MetricsLayer {
+    metrics: RpcMetrics,
+}
+
+impl MetricsLayer {
+    /// Creates a new [`MetricsLayer`] counting into `metrics`.
+    pub fn new(metrics: RpcMetrics) -> Self {
+        Self { metrics }
+    }
+}
+
+impl<S> Layer<S> for MetricsLayer {
+    type Service = MetricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        MetricsService { inner, metrics: self.metrics.clone() }
+    }
+}
+
+/// The service created by a [`MetricsLayer`].
+#[derive(Debug, Clone)]
+pub struct MetricsService<S> {
+    inner: S,
+    metrics: RpcMetrics,
+}
+
+impl<S> Service<RequestPacket> for MetricsService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let requests = match &request {
+            RequestPacket::Single(request) => std::slice::from_ref(request),
+            RequestPacket::Batch(requests) => requests.as_slice(),
+        };
+        let counters = self.metrics.counters.clone();
+        // The storage keys of each `eth_getProof` call, to count the proofs in its response.
+        let mut proof_keys = Vec::<(Id, u64)>::new();
+        for request in requests {
+            match request.method() {
+                "eth_getProof" => {
+                    let keys = storage_keys(request);
+                    counters.accounts.fetch_add(1, Ordering::Relaxed);
+                    counters.slots.fetch_add(keys, Ordering::Relaxed);
+                    proof_keys.push((request.id().clone(), keys));
+                }
+                "eth_getStorageAt" => {
+                    counters.slots.fetch_add(1, Ordering::Relaxed);
+                }
+                _ => {}
+            }
+        }
+        let calls = requests.len() as u64;
+
+        let mut inner = self.inner.clone();
+        std::mem::swap(&mut self.inner, &mut inner);
+        Box::pin(async move {
+            let response = match inner.call(request).await {
+                Ok(response) => response,
+                Err(err) => {
+                    counters.errors.fetch_add(calls, Ordering::Relaxed);
+                    return Err(err);
+                }
+            };
+            let responses = match &response {
+                ResponsePacket::Single(response) => std::slice::from_ref(response),
+                ResponsePacket::Batch(responses) => responses.as_slice(),
+            };
+
+            for response in responses {
+                let ResponsePayload::Success(payload) = &response.payload else {
+                    counters.errors.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                };
+                counters.bytes.fetch_add(payload.get().len() as u64, Ordering::Relaxed);
+                if let Some((_, keys)) = proof_keys.iter().find(|(id, _)| *id == response.id) {
+                    counters.proofs.fetch_add(1 + keys, Ordering::Relaxed);
+                }
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Returns the number of storage keys an `eth_getProof` request asks for.
+fn storage_keys(request: &SerializedRequest) -> u64 {
+    let params = request.params().map(RawValue::get).unwrap_or_default();
+    let params: Vec<Value> = serde_json::from_str(params).unwrap_or_default();
+    params.get(1).and_then(Value::as_array).map_or(0, |keys| keys.len() as u64)
+}
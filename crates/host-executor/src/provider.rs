@@ -0,0 +1,201 @@
+use std::path::{Path, PathBuf};
+
+use alloy_provider::{network::AnyNetwork, ProviderBuilder, RootProvider};
+use alloy_rpc_client::ClientBuilder;
+use alloy_transport::layers::{RetryBackoffLayer, RetryBackoffService};
+use alloy_transport_http::{reqwest::Client, Http};
+use tower::Layer;
+use url::Url;
+
+use crate::{
+    DiskCacheLayer, DiskCacheService, FailoverService, MetricsLayer, MetricsService,
+    RateLimitLayer, RateLimitService, RecordLayer, RecordService, ReplayService, RpcMetrics,
+};
+
+/// A provider whose requests are throttled, and retried with backoff when the endpoint rate
+/// limits them anyway.
+pub type RetryProvider =
+    RootProvider<RetryBackoffService<RateLimitService<Http<Client>>>, AnyNetwork>;
+
+/// A [`RetryProvider`] whose responses are also cached on disk. Cached responses aren't throttled.
+pub type CachedRetryProvider =
+    RootProvider<RetryBackoffService<DiskCacheService<RateLimitService<Http<Client>>>>, AnyNetwork>;
+
+/// A [`RetryProvider`] that fails over between several endpoints.
+pub type FailoverRetryProvider =
+    RootProvider<RetryBackoffService<RateLimitService<FailoverService<Http<Client>>>>, AnyNetwork>;
+
+/// A [`RetryProvider`] that also counts what it fetches into a [`RpcMetrics`].
+pub type MeteredRetryProvider =
+    RootProvider<RetryBackoffService<MetricsService<RateLimitService<Http<Client>>>>, AnyNetwork>;
+
+/// A [`RetryProvider`] that also records its responses into a fixture file.
+pub type RecordingRetryProvider =
+    RootProvider<RetryBackoffService<RecordService<RateLimitService<Http<Client>>>>, AnyNetwork>;
+
+/// A provider answering from a recorded fixture. See [`replay_provider`].
+pub type ReplayProvider = RootProvider<ReplayService, AnyNetwork>;
+
+/// Creates a provider answering from the fixture at `path`, as recorded by
+/// [`RetryConfig::recording_http_provider`]. Requests that weren't recorded fail.
+pub fn replay_provider(path: impl AsRef<Path>) -> eyre::Result<ReplayProvider> {
+    let client = ClientBuilder::default().transport(ReplayService::new(path)?, true);
+    Ok(ProviderBuilder::new().network::<AnyNetwork>().on_client(client))
+}
+
+/// Retry and rate limiting settings for the provider backing a [`crate::HostExecutor`].
+///
+/// Long prefetch runs issue many requests, and public endpoints tend to rate limit some of them.
+/// Requests are throttled by a [`RateLimitLayer`] if `requests_per_second` is set, and requests
+/// the endpoint still rejects as rate limited are retried with exponential backoff. Other
+/// failures, such as an unreachable endpoint, aren't retried; see [`FailoverService`] for those.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryConfig {
+    /// The maximum number of times a rate limited request is retried.
+    pub max_retries: u32,
+    /// The backoff applied before the first retry, in milliseconds.
+    pub initial_backoff_ms: u64,
+    /// The compute units per second the endpoint allows, used to space out retries.
+    pub compute_units_per_second: u64,
+    /// The maximum number of requests sent per second, or `None` to not throttle requests.
+    pub requests_per_second: Option<u32>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 10,
+            initial_backoff_ms: 1000,
+            compute_units_per_second: 330,
+            requests_per_second: None,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Sets the maximum number of retries.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the initial backoff, in milliseconds.
+    pub fn with_initial_backoff_ms(mut self, initial_backoff_ms: u64) -> Self {
+        self.initial_backoff_ms = initial_backoff_ms;
+        self
+    }
+
+    /// Sets the compute units per second budget used to space out retries.
+    pub fn with_compute_units_per_second(mut self, compute_units_per_second: u64) -> Self {
+        self.compute_units_per_second = compute_units_per_second;
+        self
+    }
+
+    /// Throttles requests to at most `requests_per_second`.
+    pub fn with_requests_per_second(mut self, requests_per_second: u32) -> Self {
+        self.requests_per_second = Some(requests_per_second);
+        self
+    }
+
+    /// Creates an HTTP provider for `url` that throttles and retries requests according to this
+    /// config.
+    ///
+    /// The returned provider can be passed to [`crate::HostExecutor::new`], so that every request
+    /// made while executing and finalizing goes through the rate limiting and retry layers.
+    pub fn http_provider(&self, url: Url) -> RetryProvider {
+        let client = ClientBuilder::default()
+            .layer(RetryBackoffLayer::new(
+                self.max_retries,
+                self.initial_backoff_ms,
+                self.compute_units_per_second,
+            ))
+            .layer(RateLimitLayer::new(self.requests_per_second))
+            .http(url);
+
+        ProviderBuilder::new().network::<AnyNetwork>().on_client(client)
+    }
+
+    /// Creates an HTTP provider for `url` that throttles and retries requests according to this
+    /// config, and caches responses for fixed blocks in `cache_dir`.
+    ///
+    /// See [`DiskCacheLayer`] for which requests are cached.
+    pub fn cached_http_provider(
+        &self,
+        url: Url,
+        cache_dir: impl Into<PathBuf>,
+    ) -> CachedRetryProvider {
+        let client = ClientBuilder::default()
+            .layer(RetryBackoffLayer::new(
+                self.max_retries,
+                self.initial_backoff_ms,
+                self.compute_units_per_second,
+            ))
+            .layer(DiskCacheLayer::new(cache_dir))
+            .layer(RateLimitLayer::new(self.requests_per_second))
+            .http(url);
+
+        ProviderBuilder::new().network::<AnyNetwork>().on_client(client)
+    }
+
+    /// Creates an HTTP provider for `url` that throttles and retries requests according to this
+    /// config, and counts the state fetched and the errors returned into `metrics`.
+    ///
+    /// Retried requests are counted on every attempt.
+    pub fn metered_http_provider(&self, url: Url, metrics: RpcMetrics) -> MeteredRetryProvider {
+        let client = ClientBuilder::default()
+            .layer(RetryBackoffLayer::new(
+                self.max_retries,
+                self.initial_backoff_ms,
+                self.compute_units_per_second,
+            ))
+            .layer(MetricsLayer::new(metrics))
+            .layer(RateLimitLayer::new(self.requests_per_second))
+            .http(url);
+
+        ProviderBuilder::new().network::<AnyNetwork>().on_client(client)
+    }
+
+    /// Creates an HTTP provider for `url` that throttles and retries requests according to this
+    /// config, and records every response into the fixture at `path`.
+    ///
+    /// The fixture can be replayed with [`replay_provider`] to build the same sketch offline.
+    pub fn recording_http_provider(
+        &self,
+        url: Url,
+        path: impl Into<PathBuf>,
+    ) -> RecordingRetryProvider {
+        let client = ClientBuilder::default()
+            .layer(RetryBackoffLayer::new(
+                self.max_retries,
+                self.initial_backoff_ms,
+                self.compute_units_per_second,
+            ))
+            .layer(RecordLayer::new(path))
+            .layer(RateLimitLayer::new(self.requests_per_second))
+            .http(url);
+
+        ProviderBuilder::new().network::<AnyNetwork>().on_client(client)
+    }
+
+    /// Creates an HTTP provider that sends requests to the first of `urls` that answers, and
+    /// throttles and retries requests according to this config. The rate limit applies to all
+    /// endpoints together.
+    ///
+    /// See [`FailoverService`] for when the next endpoint is tried.
+    pub fn failover_http_provider(
+        &self,
+        urls: impl IntoIterator<Item = Url>,
+    ) -> FailoverRetryProvider {
+        let transport = RateLimitLayer::new(self.requests_per_second)
+            .layer(FailoverService::new(urls.into_iter().map(Http::new)));
+        let client = ClientBuilder::default()
+            .layer(RetryBackoffLayer::new(
+                self.max_retries,
+                self.initial_backoff_ms,
+                self.compute_units_per_second,
+            ))
+            .transport(transport, false);
+
+        ProviderBuilder::new().network::<AnyNetwork>().on_client(client)
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use alloy_json_rpc::{RequestPacket, ResponsePacket};
+use alloy_transport::{TransportError, TransportFut};
+use tower::{Layer, Service};
+
+/// A [`Layer`] that throttles JSON-RPC requests with a token bucket.
+///
+/// The bucket holds up to one second worth of requests and refills at `requests_per_second`.
+/// Each call in a batch takes one token. When the bucket is empty, requests wait for their token
+/// instead of being sent, so long prefetch runs stay under the endpoint's limit rather than
+/// relying on retries.
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    requests_per_second: Option<u32>,
+}
+
+impl RateLimitLayer {
+    /// Creates a new [`RateLimitLayer`]. Requests aren't throttled if `requests_per_second` is
+    /// `None`.
+    pub fn new(requests_per_second: Option<u32>) -> Self {
+        Self { requests_per_second }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        let bucket = self
+            .requests_per_second
+            .filter(|requests_per_second| *requests_per_second > 0)
+            .map(|requests_per_second| Arc::new(Mutex::new(TokenBucket::new(requests_per_second))));
+        RateLimitService { inner, bucket }
+    }
+}
+
+/// The service created by a [`RateLimitLayer`]. Clones share the same bucket.
+#[derive(Debug, Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    bucket: Option<Arc<Mutex<TokenBucket>>>,
+}
+
+impl<S> Service<RequestPacket> for RateLimitService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let Some(bucket) = &self.bucket else {
+            return Box::pin(self.inner.call(request));
+        };
+
+        let calls = match &request {
+            RequestPacket::Single(_) => 1,
+            RequestPacket::Batch(requests) => requests.len(),
+        };
+        let wait = bucket.lock().expect("token bucket lock poisoned").take(calls);
+
+        let mut inner = self.inner.clone();
+        std::mem::swap(&mut self.inner, &mut inner);
+        Box::pin(async move {
+            if !wait.is_zero() {
+                tracing::debug!("rate limited, waiting {:?}", wait);
+                tokio::time::sleep(wait).await;
+            }
+            inner.call(request).await
+        })
+    }
+}
+
+/// A token bucket refilling continuously at a fixed rate.
+#[derive(Debug)]
+pub(crate) struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket holding `requests_per_second` tokens.
+    pub(crate) fn new(requests_per_second: u32) -> Self {
+        let capacity = f64::from(requests_per_second);
+        Self { capacity, tokens: capacity, refilled_at: Instant::now() }
+    }
+
+    /// Takes `count` tokens, returning how long to wait until they are available.
+    ///
+    /// Tokens are reserved even if they aren't available yet, so that concurrent requests queue
+    /// up behind each other instead of all waiting for the same refill.
+    fn take(&mut self, count: usize) -> Duration {
+        self.take_at(count, Instant::now())
+    }
+
+    /// Takes `count` tokens as of `now`. See [`TokenBucket::take`].
+    pub(crate) fn take_at(&mut self, count: usize, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.capacity).min(self.capacity);
+        self.refilled_at = now;
+
+        self.tokens -= count as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.capacity)
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::Path,
+};
+
+use alloy_provider::{network::AnyNetwork, Provider};
+use alloy_transport::Transport;
+use reth_primitives::Header;
+use revm_primitives::{AccountInfo, Address, B256, U256};
+use rsp_rpc_db::RpcDb;
+use serde::{Deserialize, Serialize};
+use sp1_cc_client_executor::genesis::Genesis;
+
+use crate::{HostExecutor, BLOCK_HASH_HISTORY};
+
+/// The in-progress state of a [`HostExecutor`].
+///
+/// A snapshot holds everything the executor has fetched so far, so that prefetching can happen in
+/// one process and finalizing in another, or be resumed after a crash without refetching.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HostExecutorSnapshot {
+    /// The chain the block belongs to.
+    pub genesis: Genesis,
+    /// The header of the block the executor runs on.
+    pub header: Header,
+    /// The accounts fetched so far.
+    pub accounts: HashMap<Address, AccountInfo>,
+    /// The storage slots fetched so far.
+    pub storage: HashMap<Address, HashMap<U256, U256>>,
+    /// The block hashes fetched so far.
+    pub block_hashes: HashMap<u64, B256>,
+    /// The oldest ancestor whose hash was requested.
+    pub oldest_ancestor: u64,
+    /// The state registered to be included in the sketch.
+    pub included_state: BTreeMap<Address, BTreeSet<U256>>,
+    /// How many blocks back calls may read with the BLOCKHASH opcode.
+    pub max_ancestor_depth: u64,
+}
+
+impl<T: Transport + Clone, P: Provider<T, AnyNetwork> + Clone> HostExecutor<T, P> {
+    /// Returns a [`HostExecutorSnapshot`] of the state fetched so far.
+    pub fn snapshot(&self) -> HostExecutorSnapshot {
+        HostExecutorSnapshot {
+            genesis: self.genesis.clone(),
+            header: self.header.clone(),
+            accounts: self.rpc_db.accounts.borrow().clone(),
+            storage: self.rpc_db.storage.borrow().clone(),
+            block_hashes: self.rpc_db.block_hashes.borrow().clone(),
+            oldest_ancestor: *self.rpc_db.oldest_ancestor.borrow(),
+            included_state: self.included_state.clone(),
+            max_ancestor_depth: self.max_ancestor_depth,
+        }
+    }
+
+    /// Restores a [`HostExecutor`] from a [`HostExecutorSnapshot`], using `provider` for any
+    /// further requests.
+    pub fn from_snapshot(provider: P, snapshot: HostExecutorSnapshot) -> Self {
+        let rpc_db = RpcDb::new(provider.clone(), snapshot.header.number);
+        *rpc_db.accounts.borrow_mut() = snapshot.accounts;
+        *rpc_db.storage.borrow_mut() = snapshot.storage;
+        *rpc_db.block_hashes.borrow_mut() = snapshot.block_hashes;
+        *rpc_db.oldest_ancestor.borrow_mut() = snapshot.oldest_ancestor;
+
+        Self {
+            genesis: snapshot.genesis,
+            header: snapshot.header,
+            rpc_db,
+            provider,
+            included_state: snapshot.included_state,
+            max_ancestor_depth: snapshot.max_ancestor_depth.min(BLOCK_HASH_HISTORY),
+        }
+    }
+
+    /// Writes a [`HostExecutorSnapshot`] of the state fetched so far to `path`.
+    pub fn save_snapshot(&self, path: impl AsRef<Path>) -> eyre::Result<()> {
+        std::fs::write(path, bincode::serialize(&self.snapshot())?)?;
+        Ok(())
+    }
+
+    /// Restores a [`HostExecutor`] from a snapshot written by [`HostExecutor::save_snapshot`].
+    pub fn load_snapshot(provider: P, path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let snapshot = bincode::deserialize(&std::fs::read(path)?)?;
+        Ok(Self::from_snapshot(provider, snapshot))
+    }
+}
@@ -1,15 +1,41 @@
-use alloy_primitives::{address, Address};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use alloy_json_rpc::{
+    Id, Request, RequestPacket, Response, ResponsePacket, ResponsePayload, SerializedRequest,
+};
+use alloy_primitives::{address, Address, B256};
 use alloy_provider::ReqwestProvider;
 use alloy_rpc_types::BlockNumberOrTag;
 use alloy_sol_macro::sol;
-use alloy_sol_types::SolCall;
-use revm_primitives::{hex, Bytes};
-use sp1_cc_client_executor::{ClientExecutor, ContractInput, ContractPublicValues};
+use alloy_sol_types::{SolCall, SolStruct, SolValue};
+use alloy_transport::{TransportError, TransportErrorKind, TransportFut};
+use revm_primitives::{hex, Bytes, U256};
+use serde_json::{json, value::RawValue};
+use sp1_cc_client_executor::{
+    genesis::Genesis, AccountOverride, ClientExecutor, ContractInput, ContractPublicValues,
+};
+use tower::{Layer, Service, ServiceExt};
 use url::Url;
 use ERC20Basic::nameCall;
 use IOracleHelper::getRatesCall;
 
-use crate::HostExecutor;
+use crate::{
+    calldata::encode_calldata, error::check_pruned_state, rate_limit::TokenBucket,
+    test_utils::LocalNode, verifier_contract, DiskCacheLayer, FailoverService, HostError,
+    HostExecutor, MetricsLayer, RecordLayer, ReplayService, RpcCounts, RpcMetrics,
+};
+
+/// The mainnet block the tests run on. It predates Prague, which isn't supported yet, so the tests
+/// need an archive node.
+const MAINNET_BLOCK: BlockNumberOrTag = BlockNumberOrTag::Number(22_400_000);
+
+/// The Sepolia block the tests run on. See [`MAINNET_BLOCK`].
+const SEPOLIA_BLOCK: BlockNumberOrTag = BlockNumberOrTag::Number(7_800_000);
 
 sol! {
     /// Simplified interface of the ERC20Basic interface.
@@ -97,6 +123,127 @@ async fn test_uniswap() -> eyre::Result<()> {
     Ok(())
 }
 
+/// This tests that prefetching through `eth_createAccessList` covers all of the state the call
+/// touches, so the client can still execute it.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_prefetch_access_list() -> eyre::Result<()> {
+    dotenv::dotenv().ok();
+
+    let slot0_call = IUniswapV3PoolState::slot0Call {};
+    let contract_input = ContractInput::new_call(
+        address!("1d42064Fc4Beb5F8aAF85F4617AE8b3b5B8Bd801"),
+        Address::default(),
+        slot0_call,
+    );
+
+    let rpc_url = std::env::var("ETH_RPC_URL").unwrap_or_else(|_| panic!("Missing RPC_URL"));
+    let provider = ReqwestProvider::new_http(Url::parse(&rpc_url)?);
+    let mut host_executor = HostExecutor::new(provider.clone(), MAINNET_BLOCK).await?;
+
+    host_executor.prefetch_access_list(&contract_input).await?;
+    let host_output = host_executor.execute(contract_input.clone()).await?;
+
+    let state_sketch = host_executor.finalize().await?;
+    let client_executor = ClientExecutor::new(state_sketch, &Genesis::Mainnet)?;
+    let public_values = client_executor.execute(contract_input)?;
+    assert_eq!(public_values.contractOutput, host_output);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_prefetch_prestate_trace() -> eyre::Result<()> {
+    dotenv::dotenv().ok();
+
+    let slot0_call = IUniswapV3PoolState::slot0Call {};
+    let contract_input = ContractInput::new_call(
+        address!("1d42064Fc4Beb5F8aAF85F4617AE8b3b5B8Bd801"),
+        Address::default(),
+        slot0_call,
+    );
+
+    let rpc_url = std::env::var("ETH_RPC_URL").unwrap_or_else(|_| panic!("Missing RPC_URL"));
+    let provider = ReqwestProvider::new_http(Url::parse(&rpc_url)?);
+    let mut host_executor = HostExecutor::new(provider.clone(), MAINNET_BLOCK).await?;
+
+    if !host_executor.prefetch_prestate_trace(&contract_input).await? {
+        // The provider doesn't support `debug_traceCall`.
+        return Ok(());
+    }
+    let host_output = host_executor.execute(contract_input.clone()).await?;
+
+    let state_sketch = host_executor.finalize().await?;
+    let client_executor = ClientExecutor::new(state_sketch, &Genesis::Mainnet)?;
+    let public_values = client_executor.execute(contract_input)?;
+    assert_eq!(public_values.contractOutput, host_output);
+
+    Ok(())
+}
+
+/// This tests that a host executor restored from a snapshot produces a sketch the client can use,
+/// without re-executing the call.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_snapshot_roundtrip() -> eyre::Result<()> {
+    dotenv::dotenv().ok();
+
+    let name_call = nameCall {};
+    let contract_input = ContractInput::new_call(
+        address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+        Address::default(),
+        name_call,
+    );
+
+    let rpc_url = std::env::var("ETH_RPC_URL").unwrap_or_else(|_| panic!("Missing RPC_URL"));
+    let provider = ReqwestProvider::new_http(Url::parse(&rpc_url)?);
+    let mut host_executor = HostExecutor::new(provider.clone(), MAINNET_BLOCK).await?;
+    host_executor.execute(contract_input.clone()).await?;
+
+    let snapshot = bincode::serialize(&host_executor.snapshot())?;
+    let host_executor = HostExecutor::from_snapshot(provider, bincode::deserialize(&snapshot)?);
+
+    let state_sketch = host_executor.finalize().await?;
+    let client_executor = ClientExecutor::new(state_sketch, &Genesis::Mainnet)?;
+    let public_values = client_executor.execute(contract_input)?;
+
+    let name = nameCall::abi_decode_returns(&public_values.contractOutput, true)?._0;
+    assert_eq!(name, String::from("Wrapped Ether"));
+
+    Ok(())
+}
+
+/// This tests that a call reading a block hash beyond the maximum ancestor depth is rejected,
+/// without affecting later calls or the headers included in the sketch.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_max_ancestor_depth() -> eyre::Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url = std::env::var("ETH_RPC_URL").unwrap_or_else(|_| panic!("Missing RPC_URL"));
+    let provider = ReqwestProvider::new_http(Url::parse(&rpc_url)?);
+    let mut host_executor =
+        HostExecutor::new(provider, MAINNET_BLOCK).await?.with_max_ancestor_depth(5);
+
+    // Deploys a contract whose code is `blockhash(block.number - 10)`.
+    let initcode = hex::decode("600a43034060005260206000f3")?;
+    let read_old_hash = ContractInput::new_create(Address::default(), Bytes::from(initcode));
+    let err = host_executor.execute(read_old_hash).await.unwrap_err();
+    assert!(matches!(err, HostError::AncestorDepthExceeded { depth: 10, max: 5 }));
+
+    let contract_input = ContractInput::new_call(
+        address!("C02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2"),
+        Address::default(),
+        nameCall {},
+    );
+    host_executor.execute(contract_input.clone()).await?;
+
+    let snapshot = host_executor.snapshot();
+    assert_eq!(snapshot.max_ancestor_depth, 5);
+
+    let state_sketch = host_executor.finalize().await?;
+    assert!(state_sketch.ancestor_headers.is_empty());
+
+    Ok(())
+}
+
 /// This test goes to the Wrapped Ether contract, and gets the name of the token.
 /// This should always be "Wrapped Ether".
 #[tokio::test(flavor = "multi_thread")]
@@ -116,19 +263,52 @@ async fn test_wrapped_eth() -> eyre::Result<()> {
     Ok(())
 }
 
+/// This tests that the host and the client agree on a call made with state overrides.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_state_overrides() -> eyre::Result<()> {
+    // Returns the value of storage slot 0.
+    let code = hex::decode("60005460005260206000f3")?;
+    let contract_address = Address::repeat_byte(0x42);
+    let account_override = AccountOverride {
+        balance: Some(U256::from(1)),
+        code: Some(Bytes::from(code)),
+        storage: [(U256::ZERO, U256::from(42))].into(),
+        ..Default::default()
+    };
+    let contract_input =
+        ContractInput::new_raw_call(contract_address, Address::default(), Bytes::new())
+            .with_override(contract_address, account_override);
+
+    dotenv::dotenv().ok();
+    let rpc_url = std::env::var("ETH_RPC_URL").unwrap_or_else(|_| panic!("Missing RPC_URL"));
+    let provider = ReqwestProvider::new_http(Url::parse(&rpc_url)?);
+    let mut host_executor = HostExecutor::new(provider.clone(), MAINNET_BLOCK).await?;
+    let host_output = host_executor.execute(contract_input.clone()).await?;
+    assert_eq!(U256::abi_decode(&host_output, true)?, U256::from(42));
+
+    let state_sketch = host_executor.finalize().await?;
+    let client_executor = ClientExecutor::new(state_sketch, &Genesis::Mainnet)?;
+    let public_values = client_executor.execute(contract_input.clone())?;
+    assert_eq!(public_values.contractOutput, host_output);
+    assert_eq!(public_values.overridesDigest, contract_input.overrides_digest());
+    assert_ne!(public_values.overridesDigest, B256::ZERO);
+
+    Ok(())
+}
+
 /// This tests contract creation transactions.
 #[tokio::test(flavor = "multi_thread")]
 async fn test_contract_creation() -> eyre::Result<()> {
     let bytecode = "0x6080604052348015600e575f5ffd5b50415f5260205ff3fe";
 
-    // Get a recent blob to get the hash from.
-    let block_number = BlockNumberOrTag::Safe;
+    let block_number = SEPOLIA_BLOCK;
 
     // Use `ETH_SEPOLIA_RPC_URL` to get all of the necessary state for the smart contract call.
     let rpc_url = std::env::var("ETH_SEPOLIA_RPC_URL")
         .unwrap_or_else(|_| panic!("Missing ETH_SEPOLIA_RPC_URL in env"));
     let provider = ReqwestProvider::new_http(Url::parse(&rpc_url)?);
-    let mut host_executor = HostExecutor::new(provider.clone(), block_number).await?;
+    let mut host_executor =
+        HostExecutor::new(provider.clone(), block_number).await?.with_genesis(Genesis::Sepolia);
 
     // Keep track of the block hash. Later, validate the client's execution against this.
     let bytes = hex::decode(bytecode).expect("Decoding failed");
@@ -148,7 +328,7 @@ async fn test_e2e(contract_input: ContractInput) -> eyre::Result<ContractPublicV
     dotenv::dotenv().ok();
 
     // Which block transactions are executed on.
-    let block_number = BlockNumberOrTag::Latest;
+    let block_number = MAINNET_BLOCK;
 
     // Prepare the host executor.
     //
@@ -162,9 +342,472 @@ async fn test_e2e(contract_input: ContractInput) -> eyre::Result<ContractPublicV
     // Now that we've executed all of the calls, get the `EVMStateSketch` from the host executor.
     let state_sketch = host_executor.finalize().await?;
 
-    let client_executor = ClientExecutor::new(state_sketch)?;
+    let client_executor = ClientExecutor::new(state_sketch, &Genesis::Mainnet)?;
 
     let public_values = client_executor.execute(contract_input)?;
 
     Ok(public_values)
 }
+
+#[test]
+fn test_encode_calldata() -> eyre::Result<()> {
+    let from_signature = encode_calldata("name()", &[])?;
+    assert_eq!(from_signature, Bytes::from(nameCall {}.abi_encode()));
+    assert_eq!(encode_calldata("0x06fdde03", &[])?, from_signature);
+
+    let from_args = encode_calldata(
+        "getRates(address[])",
+        &[concat!(
+            "[0x6B175474E89094C44Da98b954EedeAC495271d0F,",
+            "0xdAC17F958D2ee523a2206206994597C13D831ec7]"
+        )],
+    )?;
+    let expected = getRatesCall {
+        collaterals: vec![
+            address!("6B175474E89094C44Da98b954EedeAC495271d0F"),
+            address!("dAC17F958D2ee523a2206206994597C13D831ec7"),
+        ],
+    };
+    assert_eq!(from_args, Bytes::from(expected.abi_encode()));
+
+    Ok(())
+}
+
+#[test]
+fn test_solidity_public_values_match() {
+    let solidity = include_str!("../../../contracts/src/ContractCall.sol");
+
+    // The EIP-712 type is `ContractPublicValues(bytes32 domain,...)`, listing the fields in
+    // order.
+    let root_type = ContractPublicValues::eip712_root_type();
+    let fields = root_type
+        .trim_start_matches("ContractPublicValues(")
+        .trim_end_matches(')')
+        .split(',')
+        .map(|field| format!("    {field};\n"))
+        .collect::<String>();
+    let expected = format!("struct ContractPublicValues {{\n{fields}}}");
+
+    assert!(solidity.contains(&expected), "ContractCall.sol is out of sync:\n{expected}");
+}
+
+#[test]
+fn test_verifier_contract() {
+    let program_vkey = B256::repeat_byte(0xab);
+    let genesis = Genesis::Sepolia;
+    let source = verifier_contract("SepoliaVerifier", program_vkey, &genesis);
+
+    assert!(source.contains("contract SepoliaVerifier {"));
+    assert!(source.contains(&format!("bytes32 public constant PROGRAM_VKEY = {program_vkey};")));
+    assert!(source.contains("uint64 public constant CHAIN_ID = 11155111;"));
+    assert!(source.contains(&format!(
+        "bytes32 public constant CHAIN_CONFIG_HASH = {};",
+        genesis.config_hash()
+    )));
+
+    // Every check the contract makes must exist in `ContractCall.sol`.
+    let library = include_str!("../../../contracts/src/ContractCall.sol");
+    for check in ["decode", "verifyChainId", "verifyChainConfig", "verifyNoOverrides"] {
+        assert!(source.contains(&format!("ContractCall.{check}(")), "missing {check}");
+        assert!(library.contains(&format!("function {check}(")), "no {check} in the library");
+    }
+}
+
+#[test]
+fn test_public_values_json_roundtrip() -> eyre::Result<()> {
+    let call = ContractInput::new_call(Address::ZERO, Address::ZERO, nameCall {});
+    let public_values = ContractPublicValues::new(
+        call,
+        Bytes::from_static(b"output"),
+        B256::ZERO,
+        &Genesis::Mainnet,
+    );
+
+    let json = serde_json::to_string(&public_values)?;
+    assert!(json.contains("\"contractOutput\""));
+    let decoded: ContractPublicValues = serde_json::from_str(&json)?;
+    assert_eq!(decoded.abi_encode(), public_values.abi_encode());
+
+    Ok(())
+}
+
+#[test]
+fn test_check_pruned_state() {
+    let err = eyre::eyre!("missing trie node 1a2b3c (path ) <nil>")
+        .wrap_err("server returned an error response");
+    let err = HostError::from(check_pruned_state(err, 100));
+    assert!(matches!(err, HostError::ArchiveNodeRequired { block: 100 }), "{err:?}");
+
+    let err = eyre::eyre!("execution reverted").wrap_err("server returned an error response");
+    let err = HostError::from(check_pruned_state(err, 100));
+    assert!(matches!(err, HostError::Infrastructure(_)), "{err:?}");
+}
+
+#[test]
+fn test_token_bucket() {
+    let mut bucket = TokenBucket::new(10);
+    let start = Instant::now();
+
+    // A full bucket serves a burst right away.
+    assert_eq!(bucket.take_at(10, start), Duration::ZERO);
+    // Then each request waits for its own token.
+    assert_eq!(bucket.take_at(1, start), Duration::from_millis(100));
+    assert_eq!(bucket.take_at(1, start), Duration::from_millis(200));
+    // Half a second refills five tokens, two of which were already reserved.
+    assert_eq!(bucket.take_at(3, start + Duration::from_millis(500)), Duration::ZERO);
+    // The bucket never holds more than one second worth of tokens.
+    assert_eq!(bucket.take_at(11, start + Duration::from_secs(60)), Duration::from_millis(100));
+}
+
+/// A transport answering each call with `respond(method, params)`, where `Err` becomes a JSON-RPC
+/// error response. If `down` is set, every request fails at the transport level with that message
+/// instead. The methods of the calls it receives are recorded in `calls`.
+#[derive(Clone)]
+struct StubTransport {
+    respond: Arc<dyn Fn(&str, &str) -> Result<String, String> + Send + Sync>,
+    down: Option<&'static str>,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl StubTransport {
+    fn new(respond: impl Fn(&str, &str) -> Result<String, String> + Send + Sync + 'static) -> Self {
+        Self { respond: Arc::new(respond), down: None, calls: Default::default() }
+    }
+
+    fn down(message: &'static str) -> Self {
+        Self { down: Some(message), ..Self::new(|_, _| Ok("null".to_string())) }
+    }
+
+    /// Returns the methods called so far, and forgets them.
+    fn take_calls(&self) -> Vec<String> {
+        std::mem::take(&mut *self.calls.lock().unwrap())
+    }
+}
+
+impl Service<RequestPacket> for StubTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let requests = match &request {
+            RequestPacket::Single(request) => vec![request.clone()],
+            RequestPacket::Batch(requests) => requests.clone(),
+        };
+        self.calls.lock().unwrap().extend(requests.iter().map(|r| r.method().to_string()));
+        if let Some(message) = self.down {
+            return Box::pin(async move { Err(TransportErrorKind::custom_str(message)) });
+        }
+
+        let responses = requests
+            .iter()
+            .map(|request| {
+                let params = request.params().map(RawValue::get).unwrap_or_default();
+                let body = match (self.respond)(request.method(), params) {
+                    Ok(result) => json!({
+                        "jsonrpc": "2.0",
+                        "id": request.id(),
+                        "result": serde_json::from_str::<serde_json::Value>(&result).unwrap(),
+                    }),
+                    Err(message) => json!({
+                        "jsonrpc": "2.0",
+                        "id": request.id(),
+                        "error": { "code": -32000, "message": message },
+                    }),
+                };
+                serde_json::from_str::<Response>(&body.to_string()).unwrap()
+            })
+            .collect::<Vec<_>>();
+        let response = match request {
+            RequestPacket::Single(_) => {
+                ResponsePacket::Single(responses.into_iter().next().unwrap())
+            }
+            RequestPacket::Batch(_) => ResponsePacket::Batch(responses),
+        };
+        Box::pin(async move { Ok(response) })
+    }
+}
+
+fn rpc_request(id: u64, method: &'static str, params: serde_json::Value) -> SerializedRequest {
+    Request::new(method, Id::Number(id), params).serialize().unwrap()
+}
+
+/// Returns the id and the result or error message of every response in `packet`.
+fn rpc_results(packet: ResponsePacket) -> Vec<(Id, Result<String, String>)> {
+    let responses = match packet {
+        ResponsePacket::Single(response) => vec![response],
+        ResponsePacket::Batch(responses) => responses,
+    };
+    responses
+        .into_iter()
+        .map(|response| {
+            let result = match response.payload {
+                ResponsePayload::Success(payload) => Ok(payload.get().to_string()),
+                ResponsePayload::Failure(error) => Err(error.message.to_string()),
+            };
+            (response.id, result)
+        })
+        .collect()
+}
+
+/// Returns an empty directory for the disk cache tests.
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sp1-cc-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_disk_cache_keys_by_block_hash() -> eyre::Result<()> {
+    let block_hash = Arc::new(Mutex::new(B256::repeat_byte(1)));
+    let stub = StubTransport::new({
+        let block_hash = block_hash.clone();
+        move |method, _| match method {
+            "eth_getBlockByNumber" => {
+                let hash = *block_hash.lock().unwrap();
+                Ok(json!({ "number": "0x10", "hash": hash }).to_string())
+            }
+            _ => Ok(r#""0x6001""#.to_string()),
+        }
+    });
+    let dir = cache_dir("cache-block-hash");
+    let cache = DiskCacheLayer::new(&dir).layer(stub.clone());
+
+    let get_code =
+        || RequestPacket::Single(rpc_request(1, "eth_getCode", json!([Address::ZERO, "0x10"])));
+    let get_block =
+        || RequestPacket::Single(rpc_request(2, "eth_getBlockByNumber", json!(["0x10", false])));
+
+    // The hash of block 0x10 isn't known yet, so nothing is cached.
+    cache.clone().oneshot(get_code()).await?;
+    cache.clone().oneshot(get_code()).await?;
+    assert_eq!(stub.take_calls(), ["eth_getCode", "eth_getCode"]);
+
+    // Once the block is looked up, requests at it are cached. Block lookups never are.
+    cache.clone().oneshot(get_block()).await?;
+    cache.clone().oneshot(get_code()).await?;
+    let cached = cache.clone().oneshot(get_code()).await?;
+    assert_eq!(rpc_results(cached), [(Id::Number(1), Ok(r#""0x6001""#.to_string()))]);
+    cache.clone().oneshot(get_block()).await?;
+    assert_eq!(stub.take_calls(), ["eth_getBlockByNumber", "eth_getCode", "eth_getBlockByNumber"]);
+
+    // After a reorg, the block at 0x10 has another hash, and its cached state isn't served.
+    *block_hash.lock().unwrap() = B256::repeat_byte(2);
+    cache.clone().oneshot(get_block()).await?;
+    cache.clone().oneshot(get_code()).await?;
+    assert_eq!(stub.take_calls(), ["eth_getBlockByNumber", "eth_getCode"]);
+
+    // Moving tags are never cached.
+    let latest =
+        || RequestPacket::Single(rpc_request(3, "eth_getCode", json!([Address::ZERO, "latest"])));
+    cache.clone().oneshot(latest()).await?;
+    cache.clone().oneshot(latest()).await?;
+    assert_eq!(stub.take_calls(), ["eth_getCode", "eth_getCode"]);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_disk_cache_batches() -> eyre::Result<()> {
+    let stub = StubTransport::new(|method, params| match method {
+        "eth_getBlockByNumber" => {
+            Ok(json!({ "number": "0x10", "hash": B256::repeat_byte(1) }).to_string())
+        }
+        "eth_getBlockByHash" => Ok("null".to_string()),
+        _ => Ok(format!(r#""{method} {}""#, params.replace('"', "'"))),
+    });
+    let dir = cache_dir("cache-batches");
+    let cache = DiskCacheLayer::new(&dir).layer(stub.clone());
+    cache
+        .clone()
+        .oneshot(RequestPacket::Single(rpc_request(
+            0,
+            "eth_getBlockByNumber",
+            json!(["0x10", false]),
+        )))
+        .await?;
+    stub.take_calls();
+
+    let batch = |first_id: u64| {
+        RequestPacket::Batch(vec![
+            rpc_request(first_id, "eth_getCode", json!([Address::ZERO, "0x10"])),
+            rpc_request(first_id + 1, "eth_getBalance", json!([Address::ZERO, "0x10"])),
+        ])
+    };
+    let fetched = rpc_results(cache.clone().oneshot(batch(1)).await?);
+    assert_eq!(stub.take_calls(), ["eth_getCode", "eth_getBalance"]);
+
+    // The cached batch is served with the ids of the new request.
+    let cached = rpc_results(cache.clone().oneshot(batch(7)).await?);
+    assert!(stub.take_calls().is_empty());
+    assert_eq!(
+        cached.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>(),
+        [Id::Number(7), Id::Number(8)]
+    );
+    assert_eq!(
+        cached.into_iter().map(|(_, result)| result).collect::<Vec<_>>(),
+        fetched.into_iter().map(|(_, result)| result).collect::<Vec<_>>()
+    );
+
+    // A batch with a call that can't be cached is sent as a whole.
+    let mixed = || {
+        RequestPacket::Batch(vec![
+            rpc_request(1, "eth_getCode", json!([Address::ZERO, "0x10"])),
+            rpc_request(2, "eth_chainId", json!([])),
+        ])
+    };
+    cache.clone().oneshot(mixed()).await?;
+    cache.clone().oneshot(mixed()).await?;
+    assert_eq!(stub.take_calls(), ["eth_getCode", "eth_chainId", "eth_getCode", "eth_chainId"]);
+
+    // Null results aren't cached.
+    let missing_block =
+        || RequestPacket::Single(rpc_request(1, "eth_getBlockByHash", json!([B256::ZERO, false])));
+    cache.clone().oneshot(missing_block()).await?;
+    cache.clone().oneshot(missing_block()).await?;
+    assert_eq!(stub.take_calls(), ["eth_getBlockByHash", "eth_getBlockByHash"]);
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_failover() -> eyre::Result<()> {
+    let chain_id = || RequestPacket::Single(rpc_request(1, "eth_chainId", json!([])));
+    let up = StubTransport::new(|_, _| Ok(r#""0x1""#.to_string()));
+    let reverting = StubTransport::new(|_, _| Err("execution reverted".to_string()));
+    let (first, second) = (StubTransport::down("first down"), StubTransport::down("second down"));
+
+    // A transport error moves on to the next endpoint.
+    let failover = FailoverService::new([first.clone(), up.clone(), second.clone()]);
+    let response = failover.oneshot(chain_id()).await?;
+    assert_eq!(rpc_results(response), [(Id::Number(1), Ok(r#""0x1""#.to_string()))]);
+    assert_eq!(first.take_calls(), ["eth_chainId"]);
+    assert_eq!(up.take_calls(), ["eth_chainId"]);
+    assert!(second.take_calls().is_empty());
+
+    // A JSON-RPC error is an answer, and is passed through as is.
+    let failover = FailoverService::new([reverting.clone(), up.clone()]);
+    let response = failover.oneshot(chain_id()).await?;
+    assert_eq!(rpc_results(response), [(Id::Number(1), Err("execution reverted".to_string()))]);
+    assert!(up.take_calls().is_empty());
+
+    // If every endpoint fails, the last error is returned.
+    let failover = FailoverService::new([first.clone(), second.clone()]);
+    let err = failover.oneshot(chain_id()).await.unwrap_err();
+    assert_eq!(err.to_string(), TransportErrorKind::custom_str("second down").to_string());
+    assert_eq!(first.take_calls(), ["eth_chainId"]);
+    assert_eq!(second.take_calls(), ["eth_chainId"]);
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_record_replay() -> eyre::Result<()> {
+    let stub = StubTransport::new(|method, _| match method {
+        "eth_chainId" => Ok(r#""0x1""#.to_string()),
+        "eth_getCode" => Err("execution reverted".to_string()),
+        _ => Ok(r#""0x10""#.to_string()),
+    });
+    let dir = cache_dir("fixture");
+    let fixture = dir.join("fixture.json");
+    let recorder = RecordLayer::new(&fixture).layer(stub);
+
+    let batch = |first_id: u64| {
+        RequestPacket::Batch(vec![
+            rpc_request(first_id, "eth_chainId", json!([])),
+            rpc_request(first_id + 1, "eth_blockNumber", json!([])),
+        ])
+    };
+    let get_code =
+        || RequestPacket::Single(rpc_request(1, "eth_getCode", json!([Address::ZERO, "latest"])));
+    let recorded = rpc_results(recorder.clone().oneshot(batch(1)).await?);
+    recorder.clone().oneshot(get_code()).await?;
+
+    // Replayed responses match the recorded ones, with the ids of the new request.
+    let replay = ReplayService::new(&fixture)?;
+    let replayed = rpc_results(replay.clone().oneshot(batch(5)).await?);
+    assert_eq!(
+        replayed.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>(),
+        [Id::Number(5), Id::Number(6)]
+    );
+    assert_eq!(
+        replayed.into_iter().map(|(_, result)| result).collect::<Vec<_>>(),
+        recorded.into_iter().map(|(_, result)| result).collect::<Vec<_>>()
+    );
+
+    // Error responses and requests that weren't made aren't in the fixture.
+    assert!(replay.clone().oneshot(get_code()).await.is_err());
+    let unknown = RequestPacket::Single(rpc_request(1, "eth_chainId", json!(["0x1"])));
+    assert!(replay.oneshot(unknown).await.is_err());
+
+    std::fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+/// This tests the [`LocalNode`] harness against a node running at `LOCAL_RPC_URL`, for example
+/// `anvil --hardfork cancun`.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_local_node() -> eyre::Result<()> {
+    dotenv::dotenv().ok();
+
+    let rpc_url =
+        std::env::var("LOCAL_RPC_URL").unwrap_or_else(|_| panic!("Missing LOCAL_RPC_URL"));
+    let node = LocalNode::connect(Url::parse(&rpc_url)?).await?;
+
+    // Stores 42 in slot 0, and deploys code returning slot 0.
+    let init_code = Bytes::from_static(&[
+        0x60, 0x2a, 0x60, 0x00, 0x55, 0x60, 0x0b, 0x60, 0x11, 0x60, 0x00, 0x39, 0x60, 0x0b, 0x60,
+        0x00, 0xf3, 0x60, 0x00, 0x54, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+    ]);
+    let contract = node.deploy(init_code).await?;
+
+    let contract_input = ContractInput::new_raw_call(contract, Address::default(), Bytes::new());
+    let public_values = node.execute(contract_input).await?;
+    assert_eq!(U256::from_be_slice(&public_values.contractOutput), U256::from(42));
+    assert_eq!(public_values.chainId, node.genesis.chain_id());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rpc_metrics() -> eyre::Result<()> {
+    let stub = StubTransport::new(|method, _| match method {
+        "eth_getStorageAt" => Err("header not found".to_string()),
+        _ => Ok(r#""0x01""#.to_string()),
+    });
+    let metrics = RpcMetrics::default();
+    let layer = MetricsLayer::new(metrics.clone());
+
+    let batch = RequestPacket::Batch(vec![
+        rpc_request(1, "eth_getProof", json!([Address::ZERO, [B256::ZERO, B256::ZERO], "0x10"])),
+        rpc_request(2, "eth_getProof", json!([Address::ZERO, [], "0x10"])),
+        rpc_request(3, "eth_getStorageAt", json!([Address::ZERO, "0x0", "0x10"])),
+    ]);
+    layer.layer(stub).oneshot(batch).await?;
+    assert_eq!(
+        metrics.counts(),
+        RpcCounts {
+            accounts_fetched: 2,
+            slots_fetched: 3,
+            proofs_fetched: 4,
+            bytes_downloaded: 12,
+            rpc_errors: 1,
+        }
+    );
+
+    // A request failing at the transport level counts an error for each of its calls.
+    let batch = RequestPacket::Batch(vec![
+        rpc_request(1, "eth_chainId", json!([])),
+        rpc_request(2, "eth_blockNumber", json!([])),
+    ]);
+    assert!(layer.layer(StubTransport::down("down")).oneshot(batch).await.is_err());
+    assert_eq!(metrics.counts().rpc_errors, 3);
+
+    Ok(())
+}
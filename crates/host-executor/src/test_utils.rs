@@ -0,0 +1,110 @@
+//! Helpers to test contract calls end to end against a local node, such as `anvil`, instead of a
+//! live RPC endpoint.
+//!
+//! The node has to expose unlocked accounts to deploy fixtures from, and shouldn't run Prague,
+//! which isn't supported yet (e.g. `anvil --hardfork cancun`).
+
+use alloy_provider::{network::AnyNetwork, Provider, ReqwestProvider};
+use alloy_rpc_types::{BlockNumberOrTag, TransactionInput, TransactionRequest, WithOtherFields};
+use eyre::eyre;
+use revm_primitives::{Address, Bytes, TxKind};
+use sp1_cc_client_executor::{
+    genesis::Genesis, ClientExecutor, ContractInput, ContractPublicValues,
+};
+use url::Url;
+
+use crate::HostExecutor;
+
+/// A local node to deploy fixtures to and execute calls against.
+#[derive(Debug, Clone)]
+pub struct LocalNode {
+    /// The provider connected to the node.
+    pub provider: ReqwestProvider<AnyNetwork>,
+    /// The chain of the node. Its built-in [`Genesis`] if it runs one of the known chains,
+    /// otherwise a dev chain with every fork up to Cancun active at genesis.
+    pub genesis: Genesis,
+    /// The unlocked account fixtures are deployed from.
+    pub deployer: Address,
+}
+
+impl LocalNode {
+    /// Connects to the node at `url`.
+    pub async fn connect(url: Url) -> eyre::Result<Self> {
+        let provider = ReqwestProvider::new_http(url);
+        let chain_id = provider.get_chain_id().await?;
+        let genesis = match Genesis::from_chain_id(chain_id) {
+            Some(genesis) => genesis,
+            None => dev_genesis(chain_id)?,
+        };
+        let deployer = *provider
+            .get_accounts()
+            .await?
+            .first()
+            .ok_or(eyre!("the node has no unlocked accounts"))?;
+
+        Ok(Self { provider, genesis, deployer })
+    }
+
+    /// Deploys a contract with the given init code, and returns its address once mined.
+    pub async fn deploy(&self, init_code: Bytes) -> eyre::Result<Address> {
+        let request = TransactionRequest {
+            from: Some(self.deployer),
+            to: Some(TxKind::Create),
+            input: TransactionInput::new(init_code),
+            ..Default::default()
+        };
+        let receipt = self
+            .provider
+            .send_transaction(WithOtherFields::new(request))
+            .await?
+            .get_receipt()
+            .await?;
+
+        receipt.contract_address.ok_or(eyre!("deployment {} failed", receipt.transaction_hash))
+    }
+
+    /// Executes `call` at the latest block on the host, then runs the client executor natively on
+    /// the resulting sketch, as the zkVM program would.
+    ///
+    /// Returns an error if the host and the client disagree on the output.
+    pub async fn execute(&self, call: ContractInput) -> eyre::Result<ContractPublicValues> {
+        let mut host_executor = HostExecutor::new(self.provider.clone(), BlockNumberOrTag::Latest)
+            .await?
+            .with_genesis(self.genesis.clone());
+        let host_output = host_executor.execute(call.clone()).await?;
+        let state_sketch = host_executor.finalize().await?;
+
+        let public_values = ClientExecutor::new(state_sketch, &self.genesis)?.execute(call)?;
+        if public_values.contractOutput != host_output {
+            eyre::bail!(
+                "client output {} doesn't match host output {}",
+                public_values.contractOutput,
+                host_output
+            );
+        }
+        Ok(public_values)
+    }
+}
+
+/// A dev chain with the given chain id, and every fork up to Cancun active at genesis.
+fn dev_genesis(chain_id: u64) -> eyre::Result<Genesis> {
+    let config = serde_json::json!({
+        "chainId": chain_id,
+        "homesteadBlock": 0,
+        "eip150Block": 0,
+        "eip155Block": 0,
+        "eip158Block": 0,
+        "byzantiumBlock": 0,
+        "constantinopleBlock": 0,
+        "petersburgBlock": 0,
+        "istanbulBlock": 0,
+        "berlinBlock": 0,
+        "londonBlock": 0,
+        "mergeNetsplitBlock": 0,
+        "terminalTotalDifficulty": 0,
+        "terminalTotalDifficultyPassed": true,
+        "shanghaiTime": 0,
+        "cancunTime": 0,
+    });
+    Genesis::from_json(&config.to_string())
+}
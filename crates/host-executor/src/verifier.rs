@@ -0,0 +1,59 @@
+use alloy_primitives::B256;
+use sp1_cc_client_executor::genesis::Genesis;
+
+/// Generates a Solidity contract verifying proofs of the program with verification key
+/// `program_vkey`, through the SP1 verifier gateway passed to its constructor.
+///
+/// The contract imports `ContractCall.sol` from the `@sp1-contract-call` remapping, and returns
+/// the decoded [`ContractPublicValues`](sp1_cc_client_executor::ContractPublicValues) of valid
+/// proofs. Proofs of calls executed on another chain or chain config than `genesis`, or against
+/// overridden state, are rejected.
+pub fn verifier_contract(name: &str, program_vkey: B256, genesis: &Genesis) -> String {
+    let chain_id = genesis.chain_id();
+    let chain_config_hash = genesis.config_hash();
+    format!(
+        r#"// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {{ISP1Verifier}} from "@sp1-contracts/ISP1Verifier.sol";
+import {{ContractCall, ContractPublicValues}} from "@sp1-contract-call/ContractCall.sol";
+
+/// @title {name}
+/// @notice Verifies proofs of contract calls made by a single SP1 program.
+contract {name} {{
+    /// @notice The address of the SP1 verifier gateway.
+    address public immutable verifier;
+
+    /// @notice The verification key of the program.
+    bytes32 public constant PROGRAM_VKEY = {program_vkey};
+
+    /// @notice The id of the chain calls must be executed on.
+    uint64 public constant CHAIN_ID = {chain_id};
+
+    /// @notice The hash of the chain config calls must be executed with.
+    bytes32 public constant CHAIN_CONFIG_HASH = {chain_config_hash};
+
+    constructor(address _verifier) {{
+        verifier = _verifier;
+    }}
+
+    /// @notice Verifies a proof of a call on the expected chain, without state overrides, and
+    ///         returns its public values.
+    /// @param _publicValues The encoded public values.
+    /// @param _proofBytes The encoded proof.
+    function verifyContractCallProof(bytes calldata _publicValues, bytes calldata _proofBytes)
+        public
+        view
+        returns (ContractPublicValues memory)
+    {{
+        ISP1Verifier(verifier).verifyProof(PROGRAM_VKEY, _publicValues, _proofBytes);
+        ContractPublicValues memory publicValues = ContractCall.decode(_publicValues);
+        ContractCall.verifyChainId(publicValues, CHAIN_ID);
+        ContractCall.verifyChainConfig(publicValues, CHAIN_CONFIG_HASH);
+        ContractCall.verifyNoOverrides(publicValues);
+        return publicValues;
+    }}
+}}
+"#
+    )
+}
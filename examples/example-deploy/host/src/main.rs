@@ -7,7 +7,7 @@ use alloy_primitives::{Address, Bytes};
 use alloy_provider::ReqwestProvider;
 use alloy_rpc_types::BlockNumberOrTag;
 use alloy_sol_types::SolValue;
-use sp1_cc_client_executor::ContractInput;
+use sp1_cc_client_executor::{genesis::Genesis, ContractInput};
 use sp1_cc_host_executor::HostExecutor;
 use url::Url;
 
@@ -32,14 +32,15 @@ const BYTECODE: &str = "0x6080604052348015600e575f5ffd5b50415f5260205ff3fe";
 
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
-    // Get a recent blob to get the hash from.
-    let block_number = BlockNumberOrTag::Safe;
+    // A block before Prague, which isn't supported yet.
+    let block_number = BlockNumberOrTag::Number(7_800_000);
 
     // Use `ETH_SEPOLIA_RPC_URL` to get all of the necessary state for the smart contract call.
     let rpc_url = std::env::var("ETH_SEPOLIA_RPC_URL")
         .unwrap_or_else(|_| panic!("Missing ETH_SEPOLIA_RPC_URL in env"));
     let provider = ReqwestProvider::new_http(Url::parse(&rpc_url)?);
-    let mut host_executor = HostExecutor::new(provider.clone(), block_number).await?;
+    let mut host_executor =
+        HostExecutor::new(provider.clone(), block_number).await?.with_genesis(Genesis::Sepolia);
 
     // Keep track of the block hash. Later, validate the client's execution against this.
     let bytes = hex::decode(BYTECODE).expect("Decoding failed");
@@ -44,8 +44,8 @@ async fn main() -> eyre::Result<()> {
     // Setup logging.
     utils::setup_logger();
 
-    // Which block transactions are executed on.
-    let block_number = BlockNumberOrTag::Safe;
+    // Which block transactions are executed on. It predates Prague, which isn't supported yet.
+    let block_number = BlockNumberOrTag::Number(22_400_000);
 
     // Prepare the host executor.
     //
@@ -72,7 +72,7 @@ async fn main() -> eyre::Result<()> {
     let input = host_executor.finalize().await?;
 
     // Feed the sketch into the client.
-    let input_bytes = bincode::serialize(&input)?;
+    let input_bytes = input.to_bytes()?;
     let mut stdin = SP1Stdin::new();
     stdin.write(&input_bytes);
 
@@ -2,15 +2,7 @@
 pragma solidity ^0.8.20;
 
 import {ISP1Verifier} from "@sp1-contracts/ISP1Verifier.sol";
-
-/// The public values returned by the contract call execution.
-struct ContractPublicValues {
-    bytes32 blockHash;
-    address callerAddress;
-    address contractAddress;
-    bytes contractCalldata;
-    bytes contractOutput;
-}
+import {ContractCall, ContractPublicValues} from "@sp1-contract-call/ContractCall.sol";
 
 /// @title SP1 UniswapCall.
 /// @notice This contract implements a simple example of verifying the proof of call to a smart 
@@ -36,7 +28,9 @@ contract UniswapCall {
         returns (uint160)
     {
         ISP1Verifier(verifier).verifyProof(uniswapCallProgramVKey, _publicValues, _proofBytes);
-        ContractPublicValues memory publicValues = abi.decode(_publicValues, (ContractPublicValues));
+        ContractPublicValues memory publicValues = ContractCall.decode(_publicValues);
+        ContractCall.verifyNoOverrides(publicValues);
+        ContractCall.verifyChainId(publicValues, 1);
         uint160 sqrtPriceX96 = abi.decode(publicValues.contractOutput, (uint160));
         return sqrtPriceX96;
     }
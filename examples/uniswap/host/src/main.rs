@@ -95,7 +95,7 @@ async fn main() -> eyre::Result<()> {
     let input = host_executor.finalize().await?;
 
     // Feed the sketch into the client.
-    let input_bytes = bincode::serialize(&input)?;
+    let input_bytes = input.to_bytes()?;
     let mut stdin = SP1Stdin::new();
     stdin.write(&input_bytes);
 
@@ -4,8 +4,7 @@ sp1_zkvm::entrypoint!(main);
 use alloy_primitives::{address, Address, Bytes, B256};
 use alloy_sol_macro::sol;
 use alloy_sol_types::SolValue;
-use bincode;
-use sp1_cc_client_executor::{io::EVMStateSketch, ClientExecutor, ContractInput};
+use sp1_cc_client_executor::{genesis::Genesis, io::EVMStateSketch, ClientExecutor, ContractInput};
 
 sol! {
     /// Part of the SimpleStaking interface
@@ -23,7 +22,7 @@ pub fn main() {
     // Read the state sketch from stdin. Use this during the execution in order to
     // access Ethereum state.
     let state_sketch_bytes = sp1_zkvm::io::read::<Vec<u8>>();
-    let state_sketch = bincode::deserialize::<EVMStateSketch>(&state_sketch_bytes).unwrap();
+    let state_sketch = EVMStateSketch::from_bytes(&state_sketch_bytes).unwrap();
 
     // Read messages and signatures from stdin.
     let messages = sp1_zkvm::io::read::<Vec<B256>>();
@@ -31,7 +30,7 @@ pub fn main() {
 
     // Initialize the client executor with the state sketch.
     // This step also validates all of the storage against the provided state root.
-    let executor = ClientExecutor::new(state_sketch).unwrap();
+    let executor = ClientExecutor::new(state_sketch, &Genesis::Sepolia).unwrap();
 
     // Set up the call to `verifySigned`.
     let verify_signed_call = ContractInput::new_call(
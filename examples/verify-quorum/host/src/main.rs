@@ -7,7 +7,7 @@ use rand_chacha::ChaCha20Rng;
 use rand_core::SeedableRng;
 use reth_primitives::public_key_to_address;
 use secp256k1::{generate_keypair, Message, SECP256K1};
-use sp1_cc_client_executor::{ContractInput, ContractPublicValues};
+use sp1_cc_client_executor::{genesis::Genesis, ContractInput, ContractPublicValues};
 use sp1_cc_host_executor::HostExecutor;
 use sp1_sdk::{utils, ProverClient, SP1Stdin};
 use url::Url;
@@ -43,8 +43,8 @@ async fn main() -> eyre::Result<()> {
     // Setup logging.
     utils::setup_logger();
 
-    // Which block transactions are executed on.
-    let block_number = BlockNumberOrTag::Latest;
+    // Which block transactions are executed on. It predates Prague, which isn't supported yet.
+    let block_number = BlockNumberOrTag::Number(7_800_000);
 
     // The testing rng we use to generate messages and secret keys.
     //
@@ -57,7 +57,8 @@ async fn main() -> eyre::Result<()> {
     let rpc_url = std::env::var("ETH_SEPOLIA_RPC_URL")
         .unwrap_or_else(|_| panic!("Missing ETH_SEPOLIA_RPC_URL in env"));
     let provider = ReqwestProvider::new_http(Url::parse(&rpc_url)?);
-    let mut host_executor = HostExecutor::new(provider.clone(), block_number).await?;
+    let mut host_executor =
+        HostExecutor::new(provider.clone(), block_number).await?.with_genesis(Genesis::Sepolia);
 
     // Keep track of the block hash. Later, validate the client's execution against this.
     let block_hash = host_executor.header.hash_slow();
@@ -112,7 +113,7 @@ async fn main() -> eyre::Result<()> {
     let input = host_executor.finalize().await?;
 
     // Feed the sketch into the client.
-    let input_bytes = bincode::serialize(&input)?;
+    let input_bytes = input.to_bytes()?;
     let mut stdin = SP1Stdin::new();
     stdin.write(&input_bytes);
 